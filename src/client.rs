@@ -4,7 +4,7 @@ use std::sync::{
 };
 
 use anyhow::{bail, Ok, Result};
-use crossbeam_channel::Sender;
+use crossbeam_channel::{Receiver, Sender};
 use dashmap::DashMap;
 use lsp_server::{ErrorCode, Message, Request, RequestId, Response};
 use serde::{de::DeserializeOwned, Serialize};
@@ -48,6 +48,26 @@ impl LspClient {
         R: lsp_types::request::Request,
         R::Params: Serialize,
         R::Result: DeserializeOwned,
+    {
+        let (_, rx) = self.send_request_async::<R>(params)?;
+        let response = rx.recv()?;
+        let result = match response.error {
+            Some(error) => bail!(error.message),
+            None => response.result.unwrap_or_default(),
+        };
+
+        Ok(serde_json::from_value(result)?)
+    }
+
+    /// Send a request without waiting for its response. Return the id of the request and the
+    /// receiver of its response.
+    pub fn send_request_async<R>(
+        &self,
+        params: R::Params,
+    ) -> Result<(RequestId, Receiver<Response>)>
+    where
+        R: lsp_types::request::Request,
+        R::Params: Serialize,
     {
         let id = RequestId::from(self.raw.next_id.fetch_add(1, Ordering::SeqCst));
 
@@ -56,25 +76,21 @@ impl LspClient {
 
         self.raw
             .sender
-            .send(Request::new(id, R::METHOD.to_string(), params).into())?;
+            .send(Request::new(id.clone(), R::METHOD.to_string(), params).into())?;
 
-        let response = rx.recv()?;
-        let result = match response.error {
-            Some(error) => bail!(error.message),
-            None => response.result.unwrap_or_default(),
-        };
+        Ok((id, rx))
+    }
 
-        Ok(serde_json::from_value(result)?)
+    /// Stop waiting for the response of a request, which is discarded when it is received.
+    pub fn forget_request(&self, id: &RequestId) {
+        self.raw.pending.remove(id);
     }
 
     pub fn recv_response(&self, response: lsp_server::Response) -> Result<()> {
-        let (_, tx) = self
-            .raw
-            .pending
-            .remove(&response.id)
-            .expect("response with known request id received");
-
-        tx.send(response)?;
+        // Forgotten requests have no pending sender.
+        if let Some((_, tx)) = self.raw.pending.remove(&response.id) {
+            tx.send(response)?;
+        }
         Ok(())
     }
 
@@ -88,3 +104,85 @@ impl LspClient {
         Ok(())
     }
 }
+
+#[cfg(test)]
+mod tests {
+    use crossbeam_channel::TryRecvError;
+    use lsp_server::{ErrorCode, Message, RequestId, Response};
+    use lsp_types::{
+        request::WorkDoneProgressCreate, NumberOrString, WorkDoneProgressCreateParams,
+    };
+
+    use super::LspClient;
+
+    fn send_create_request(
+        client: &LspClient,
+    ) -> (RequestId, crossbeam_channel::Receiver<Response>) {
+        client
+            .send_request_async::<WorkDoneProgressCreate>(WorkDoneProgressCreateParams {
+                token: NumberOrString::Number(0),
+            })
+            .unwrap()
+    }
+
+    #[test]
+    fn recv_response_routes_the_response() {
+        let (tx, rx) = crossbeam_channel::unbounded();
+        let client = LspClient::new(tx);
+        let (id, response_rx) = send_create_request(&client);
+        assert!(matches!(rx.try_recv(), Ok(Message::Request(request)) if request.id == id));
+
+        client
+            .recv_response(Response::new_ok(id.clone(), ()))
+            .unwrap();
+        let response = response_rx.try_recv().unwrap();
+        assert_eq!(response.id, id);
+        assert!(response.error.is_none());
+    }
+
+    #[test]
+    fn recv_response_routes_the_errors() {
+        let (tx, _rx) = crossbeam_channel::unbounded();
+        let client = LspClient::new(tx);
+        let (id, response_rx) = send_create_request(&client);
+
+        client
+            .recv_response(Response::new_err(
+                id,
+                ErrorCode::InternalError as i32,
+                "Failed".to_string(),
+            ))
+            .unwrap();
+        let error = response_rx.try_recv().unwrap().error.unwrap();
+        assert_eq!(error.message, "Failed");
+    }
+
+    #[test]
+    fn recv_response_ignores_forgotten_requests() {
+        let (tx, _rx) = crossbeam_channel::unbounded();
+        let client = LspClient::new(tx);
+        let (id, response_rx) = send_create_request(&client);
+
+        client.forget_request(&id);
+        assert!(matches!(
+            response_rx.try_recv(),
+            Err(TryRecvError::Disconnected)
+        ));
+        client.recv_response(Response::new_ok(id, ())).unwrap();
+        client
+            .recv_response(Response::new_ok(RequestId::from(42), ()))
+            .unwrap();
+    }
+
+    #[test]
+    fn send_request_async_fails_when_disconnected() {
+        let (tx, rx) = crossbeam_channel::unbounded();
+        drop(rx);
+        let client = LspClient::new(tx);
+        assert!(client
+            .send_request_async::<WorkDoneProgressCreate>(WorkDoneProgressCreateParams {
+                token: NumberOrString::Number(0),
+            })
+            .is_err());
+    }
+}
@@ -4,19 +4,20 @@ use std::{
 };
 
 use anyhow::anyhow;
-use fxhash::{FxHashMap, FxHashSet};
+use fxhash::FxHashMap;
 use lazy_static::lazy_static;
 use lsp_types::Range;
 use lsp_types::Url;
+use serde::{Deserialize, Serialize};
 use sourcepawn_preprocessor::preprocessor::{Macro, Offset};
 use strip_bom::StripBom;
-use tree_sitter::{Node, Query, QueryCursor};
+use tree_sitter::{Node, Query, QueryCursor, Tree};
 
 use crate::{
     linter::document_diagnostics::DocumentDiagnostics,
     parser::comment_parser::{Comment, Deprecated},
     spitem::SPItem,
-    utils::ts_range_to_lsp_range,
+    utils::{diff_input_edit, ts_range_to_lsp_range},
 };
 
 lazy_static! {
@@ -37,13 +38,13 @@ lazy_static! {
     .expect("Could not build methods query.");
 }
 
-#[derive(Debug, Clone)]
+#[derive(Debug, Clone, Serialize, Deserialize)]
 pub(crate) enum SPToken {
     Symbol(Arc<Token>),
     Method((Arc<Token>, Arc<Token>)),
 }
 
-#[derive(Debug, Clone)]
+#[derive(Debug, Clone, Serialize, Deserialize)]
 pub(crate) struct Token {
     pub(crate) text: String,
     pub(crate) range: Range,
@@ -72,16 +73,23 @@ pub struct Document {
     pub parsed: bool,
     pub(crate) tokens: Vec<SPToken>,
     pub missing_includes: FxHashMap<String, Range>,
-    pub unresolved_tokens: FxHashSet<String>,
+    /// Tokens which could not be resolved, with the user visible ranges of their occurrences.
+    pub unresolved_tokens: FxHashMap<String, Vec<Range>>,
     pub declarations: FxHashMap<String, Arc<RwLock<SPItem>>>,
     pub diagnostics: DocumentDiagnostics,
     pub(crate) macros: FxHashMap<String, Macro>,
     pub(crate) macro_symbols: Vec<Arc<Token>>,
     pub(crate) offsets: FxHashMap<u32, Vec<Offset>>,
+    pub(crate) comment_blocks: Vec<Range>,
+    pub(crate) conditional_blocks: Vec<Range>,
+    pub(crate) inactive_ranges: Vec<Range>,
+    /// Tree of the preprocessed text, kept to reparse the document incrementally.
+    pub(crate) tree: Option<Tree>,
 }
 
 pub struct Walker {
     pub comments: Vec<Comment>,
+    pub comment_blocks: Vec<Range>,
     pub deprecated: Vec<Deprecated>,
     pub anon_enum_counter: u32,
 }
@@ -98,13 +106,33 @@ impl Document {
             parsed: false,
             tokens: vec![],
             missing_includes: FxHashMap::default(),
-            unresolved_tokens: FxHashSet::default(),
+            unresolved_tokens: FxHashMap::default(),
             declarations: FxHashMap::default(),
             diagnostics: DocumentDiagnostics::default(),
             macros: FxHashMap::default(),
             macro_symbols: vec![],
             offsets: FxHashMap::default(),
+            comment_blocks: vec![],
+            conditional_blocks: vec![],
+            inactive_ranges: vec![],
+            tree: None,
+        }
+    }
+
+    /// Return a copy of the tree of the document, edited to match a new version of its
+    /// preprocessed text, in order to reparse it incrementally.
+    ///
+    /// # Arguments
+    ///
+    /// * `preprocessed_text` - New preprocessed text of the document.
+    pub(crate) fn edited_tree(&self, preprocessed_text: &str) -> Option<Tree> {
+        if self.preprocessed_text.is_empty() {
+            return None;
         }
+        let mut tree = self.tree.clone()?;
+        tree.edit(&diff_input_edit(&self.preprocessed_text, preprocessed_text));
+
+        Some(tree)
     }
 
     pub fn text(&self) -> &str {
@@ -0,0 +1,373 @@
+use std::{
+    env, fs, io,
+    path::PathBuf,
+    sync::{Arc, RwLock},
+    time::SystemTime,
+};
+
+use fxhash::{FxHashMap, FxHashSet};
+use lsp_types::{Diagnostic, Range, Url};
+use serde::{Deserialize, Serialize};
+use sourcepawn_preprocessor::preprocessor::{Macro, Offset};
+
+use crate::{
+    document::{Document, SPToken, Token},
+    spitem::SPItem,
+    store::Store,
+};
+
+/// Parsed state of a [Document] of an include directory, written to the disk so that the file
+/// does not have to be parsed again when the server restarts.
+#[derive(Debug, Serialize, Deserialize)]
+struct CachedDocument {
+    /// Version of the server which wrote the entry.
+    version: String,
+
+    /// [Uri](Url) of the document, used to prune the entries of the deleted files.
+    uri: Url,
+
+    /// Hash of the `predefinedMacros` setting when the document was preprocessed.
+    predefined_macros_hash: u64,
+
+    /// Last modification time of the file when it was parsed.
+    mtime: SystemTime,
+
+    /// Hash of the text of the file when it was parsed.
+    text_hash: u64,
+
+    preprocessed_text: String,
+    sp_items: Vec<Arc<RwLock<SPItem>>>,
+    includes: FxHashMap<Url, Token>,
+    missing_includes: FxHashMap<String, Range>,
+    tokens: Vec<SPToken>,
+    macros: FxHashMap<String, CachedMacro>,
+    macro_symbols: Vec<Arc<Token>>,
+    offsets: FxHashMap<u32, Vec<(u32, i32)>>,
+    comment_blocks: Vec<Range>,
+    conditional_blocks: Vec<Range>,
+    inactive_ranges: Vec<Range>,
+    local_diagnostics: Vec<Diagnostic>,
+}
+
+/// Header of a [CachedDocument], read to prune the cache without reading the whole entries.
+#[derive(Debug, Deserialize)]
+struct CachedDocumentUri {
+    uri: Url,
+}
+
+/// [Macro] stored as the text of its body, which is lexed again when the cache is loaded.
+#[derive(Debug, Serialize, Deserialize)]
+struct CachedMacro {
+    params: Option<Vec<i8>>,
+    body: String,
+}
+
+impl CachedDocument {
+    fn new(document: &Document, mtime: SystemTime, predefined_macros_hash: u64) -> Self {
+        Self {
+            version: env!("CARGO_PKG_VERSION").to_string(),
+            uri: (*document.uri).clone(),
+            predefined_macros_hash,
+            mtime,
+            text_hash: fxhash::hash64(&document.text),
+            preprocessed_text: document.preprocessed_text.clone(),
+            sp_items: document.sp_items.clone(),
+            includes: document.includes.clone(),
+            missing_includes: document.missing_includes.clone(),
+            tokens: document.tokens.clone(),
+            macros: document
+                .macros
+                .iter()
+                .map(|(name, macro_)| {
+                    (
+                        name.clone(),
+                        CachedMacro {
+                            params: macro_.params().cloned(),
+                            body: macro_.body_text(),
+                        },
+                    )
+                })
+                .collect(),
+            macro_symbols: document.macro_symbols.clone(),
+            offsets: document
+                .offsets
+                .iter()
+                .map(|(line, offsets)| {
+                    (
+                        *line,
+                        offsets
+                            .iter()
+                            .map(|offset| (offset.col, offset.diff))
+                            .collect(),
+                    )
+                })
+                .collect(),
+            comment_blocks: document.comment_blocks.clone(),
+            conditional_blocks: document.conditional_blocks.clone(),
+            inactive_ranges: document.inactive_ranges.clone(),
+            local_diagnostics: document.diagnostics.local_diagnostics.clone(),
+        }
+    }
+
+    /// Copy the cached state into a [Document] which has been read but not parsed.
+    ///
+    /// # Arguments
+    ///
+    /// * `document` - [Document] to restore.
+    fn restore(self, document: &mut Document) {
+        for item in self.sp_items.iter() {
+            restore_parents(item);
+            let item_lock = item.read().unwrap();
+            match &*item_lock {
+                SPItem::Include(_) => continue,
+                SPItem::Methodmap(_) | SPItem::EnumStruct(_) => {
+                    // The methods and properties are declarations, unlike the fields.
+                    for child in item_lock.children().into_iter().flatten() {
+                        let child_lock = child.read().unwrap();
+                        if matches!(&*child_lock, SPItem::Function(_) | SPItem::Property(_)) {
+                            document
+                                .declarations
+                                .insert(child_lock.key(), child.clone());
+                        }
+                    }
+                }
+                _ => (),
+            }
+            document.declarations.insert(item_lock.key(), item.clone());
+        }
+        document.preprocessed_text = self.preprocessed_text;
+        document.sp_items = self.sp_items;
+        document.includes = self.includes;
+        document.missing_includes = self.missing_includes;
+        document.tokens = self.tokens;
+        document.macros = self
+            .macros
+            .into_iter()
+            .map(|(name, macro_)| (name, Macro::from_text(macro_.params, &macro_.body)))
+            .collect();
+        document.macro_symbols = self.macro_symbols;
+        document.offsets = self
+            .offsets
+            .into_iter()
+            .map(|(line, offsets)| {
+                (
+                    line,
+                    offsets
+                        .into_iter()
+                        .map(|(col, diff)| Offset { col, diff })
+                        .collect(),
+                )
+            })
+            .collect();
+        document.comment_blocks = self.comment_blocks;
+        document.conditional_blocks = self.conditional_blocks;
+        document.inactive_ranges = self.inactive_ranges;
+        document.diagnostics.local_diagnostics = self.local_diagnostics;
+        document.parsed = true;
+    }
+}
+
+/// Set the parent of the children of an item, as they are not serialized.
+///
+/// # Arguments
+///
+/// * `item` - [Item](SPItem) whose children to update.
+fn restore_parents(item: &Arc<RwLock<SPItem>>) {
+    let children = match item.read().unwrap().children() {
+        Some(children) => children.clone(),
+        None => return,
+    };
+    for child in children.iter() {
+        match &mut *child.write().unwrap() {
+            SPItem::Function(function_item) => function_item.parent = Some(Arc::downgrade(item)),
+            SPItem::Variable(variable_item) => variable_item.parent = Some(Arc::downgrade(item)),
+            SPItem::Property(property_item) => property_item.parent = Arc::downgrade(item),
+            SPItem::EnumMember(enum_member_item) => enum_member_item.parent = Arc::downgrade(item),
+            _ => (),
+        }
+        restore_parents(child);
+    }
+}
+
+/// Return the directory where the index cache is stored, in the cache directory of the user.
+fn index_cache_dir() -> PathBuf {
+    let cache_dir = if cfg!(windows) {
+        env::var_os("LOCALAPPDATA").map(PathBuf::from)
+    } else if cfg!(target_os = "macos") {
+        env::var_os("HOME").map(|home| PathBuf::from(home).join("Library").join("Caches"))
+    } else {
+        env::var_os("XDG_CACHE_HOME")
+            .map(PathBuf::from)
+            .filter(|path| path.is_absolute())
+            .or_else(|| env::var_os("HOME").map(|home| PathBuf::from(home).join(".cache")))
+    };
+    let cache_dir = cache_dir.unwrap_or_else(|| {
+        // Do not share the entries with the other users of the temporary directory.
+        let user = env::var("USER")
+            .or_else(|_| env::var("USERNAME"))
+            .unwrap_or_default();
+        env::temp_dir().join(format!("sourcepawn-lsp-{}", user))
+    });
+
+    cache_dir.join("sourcepawn-lsp").join("index")
+}
+
+/// Remove the entries of the cache whose files no longer exist. The entries which can't be read,
+/// e.g because they were written by an older version, are removed as well.
+fn prune_index_cache() {
+    let Ok(entries) = fs::read_dir(index_cache_dir()) else {
+        return;
+    };
+    for entry in entries.flatten() {
+        let path = entry.path();
+        let is_stale = fs::File::open(&path)
+            .ok()
+            .and_then(|file| {
+                serde_json::from_reader::<_, CachedDocumentUri>(io::BufReader::new(file)).ok()
+            })
+            .and_then(|header| header.uri.to_file_path().ok())
+            .map_or(true, |file_path| !file_path.exists());
+        if is_stale {
+            log::debug!("Pruning the index cache entry {:?}.", path);
+            let _ = fs::remove_file(path);
+        }
+    }
+}
+
+/// Return the path of the cache entry of a document.
+///
+/// # Arguments
+///
+/// * `uri` - [Uri](Url) of the document.
+fn entry_path(uri: &Url) -> PathBuf {
+    index_cache_dir().join(format!("{:016x}.json", fxhash::hash64(uri.as_str())))
+}
+
+/// Return the last modification time of a document on the disk.
+///
+/// # Arguments
+///
+/// * `uri` - [Uri](Url) of the document.
+fn modified_time(uri: &Url) -> Option<SystemTime> {
+    fs::metadata(uri.to_file_path().ok()?).ok()?.modified().ok()
+}
+
+impl Store {
+    /// Restore the documents of the include directories which did not change since they were
+    /// cached. Entries are only restored if the files they include are restored as well, since
+    /// their macros affect the preprocessing of the document.
+    ///
+    /// Return the [uris](Url) of the restored documents.
+    pub(crate) fn load_index_cache(&mut self) -> FxHashSet<Arc<Url>> {
+        if self.environment.options.disable_index_cache {
+            return FxHashSet::default();
+        }
+        let predefined_macros_hash = fxhash::hash64(&self.environment.options.predefined_macros);
+        let mut entries: FxHashMap<Arc<Url>, CachedDocument> = FxHashMap::default();
+        for document in self.documents.values() {
+            if document.parsed || !self.is_in_includes_directories(&document.uri) {
+                continue;
+            }
+            let entry: CachedDocument = match fs::read(entry_path(&document.uri))
+                .ok()
+                .and_then(|data| serde_json::from_slice(&data).ok())
+            {
+                Some(entry) => entry,
+                None => continue,
+            };
+            if entry.version == env!("CARGO_PKG_VERSION")
+                && entry.predefined_macros_hash == predefined_macros_hash
+                && Some(entry.mtime) == modified_time(&document.uri)
+                && entry.text_hash == fxhash::hash64(&document.text)
+            {
+                entries.insert(document.uri.clone(), entry);
+            }
+        }
+        loop {
+            let outdated: Vec<Arc<Url>> = entries
+                .iter()
+                .filter(|(_, entry)| entry.includes.keys().any(|uri| !entries.contains_key(uri)))
+                .map(|(uri, _)| uri.clone())
+                .collect();
+            if outdated.is_empty() {
+                break;
+            }
+            for uri in outdated.iter() {
+                entries.remove(uri);
+            }
+        }
+
+        let mut restored = FxHashSet::default();
+        for (uri, entry) in entries {
+            if let Some(document) = self.documents.get_mut(&uri) {
+                entry.restore(document);
+                restored.insert(uri);
+            }
+        }
+        log::debug!(
+            "Restored {} document(s) from the index cache.",
+            restored.len()
+        );
+
+        restored
+    }
+
+    /// Write the parsed documents of the include directories to the cache. This has to be called
+    /// before the references are resolved, as the resolution discards the offsets of the
+    /// preprocessor of these documents.
+    ///
+    /// # Arguments
+    ///
+    /// * `restored` - [Uris](Url) of the documents restored from the cache, which are skipped.
+    pub(crate) fn save_index_cache(&self, restored: &FxHashSet<Arc<Url>>) {
+        if self.environment.options.disable_index_cache {
+            return;
+        }
+        if let Err(err) = fs::create_dir_all(index_cache_dir()) {
+            log::error!("Failed to create the index cache directory: {}", err);
+            return;
+        }
+        prune_index_cache();
+        let predefined_macros_hash = fxhash::hash64(&self.environment.options.predefined_macros);
+        for document in self.documents.values() {
+            if !document.parsed
+                || restored.contains(&document.uri)
+                || !self.is_in_includes_directories(&document.uri)
+            {
+                continue;
+            }
+            let mtime = match modified_time(&document.uri) {
+                Some(mtime) => mtime,
+                None => continue,
+            };
+            let entry = CachedDocument::new(document, mtime, predefined_macros_hash);
+            let result = serde_json::to_vec(&entry)
+                .map_err(anyhow::Error::from)
+                .and_then(|data| Ok(fs::write(entry_path(&document.uri), data)?));
+            if let Err(err) = result {
+                log::error!(
+                    "Failed to write the index cache of {:?}: {}",
+                    document.uri,
+                    err
+                );
+            }
+        }
+    }
+
+    /// Check if a document is in one of the include directories.
+    ///
+    /// # Arguments
+    ///
+    /// * `uri` - [Uri](Url) of the document.
+    pub(crate) fn is_in_includes_directories(&self, uri: &Url) -> bool {
+        let path = match uri.to_file_path() {
+            Ok(path) => path,
+            Err(_) => return false,
+        };
+        self.environment
+            .options
+            .includes_directories
+            .iter()
+            .any(|include_directory| path.starts_with(include_directory))
+    }
+}
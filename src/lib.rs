@@ -3,6 +3,7 @@ mod client;
 mod dispatch;
 mod document;
 mod environment;
+mod index_cache;
 mod line_index;
 mod line_index_ext;
 mod linter;
@@ -0,0 +1,390 @@
+use lsp_types::{Diagnostic, DiagnosticSeverity, Range};
+use tree_sitter::Node;
+
+use crate::{
+    document::Document,
+    semantic_analyzer::type_resolver::{member_access_nodes, ExpressionType, TypeResolver},
+    spitem::{function_item::FunctionItem, variable_item::VariableStorageClass, SPItem},
+    utils::ts_range_to_lsp_range,
+};
+
+/// Lightweight checker of the calls and of the assignments of a document, which relies on the
+/// [TypeResolver] to find the items the expressions refer to.
+pub(crate) struct Checker<'a, 'b> {
+    /// Resolver of the types of the expressions of the document.
+    resolver: &'b TypeResolver<'a>,
+
+    /// Document to check.
+    document: &'a Document,
+
+    /// Diagnostics found so far, with ranges in the preprocessed text.
+    diagnostics: Vec<Diagnostic>,
+}
+
+impl<'a, 'b> Checker<'a, 'b> {
+    /// Create a checker for a document.
+    ///
+    /// # Arguments
+    ///
+    /// * `resolver` - [TypeResolver] of the document.
+    /// * `document` - [Document] to check.
+    pub(crate) fn new(resolver: &'b TypeResolver<'a>, document: &'a Document) -> Self {
+        Self {
+            resolver,
+            document,
+            diagnostics: vec![],
+        }
+    }
+
+    /// Check the whole document and return the diagnostics, with user visible ranges.
+    pub(crate) fn check(mut self) -> Vec<Diagnostic> {
+        let Some(tree) = &self.document.tree else {
+            return vec![];
+        };
+        let mut nodes = vec![tree.root_node()];
+        while let Some(node) = nodes.pop() {
+            match node.kind() {
+                "call_expression" if !node.has_error() => self.check_call(node),
+                "assignment_expression" => {
+                    if let Some(left_node) = node
+                        .child_by_field_name("left")
+                        .or_else(|| node.named_child(0))
+                    {
+                        self.check_assignment(left_node);
+                    }
+                }
+                "update_expression" => {
+                    if let Some(argument_node) = node.named_child(0) {
+                        self.check_assignment(argument_node);
+                    }
+                }
+                _ => (),
+            }
+            let mut cursor = node.walk();
+            nodes.extend(node.named_children(&mut cursor));
+        }
+
+        let document = self.document;
+        self.diagnostics
+            .into_iter()
+            .map(|mut diagnostic| {
+                diagnostic.range = document.build_v_range(&diagnostic.range);
+                diagnostic
+            })
+            .collect()
+    }
+
+    /// Check the number and the tags of the arguments of a call, and that the called method
+    /// exists.
+    fn check_call(&mut self, node: Node) {
+        let Some(function_node) = node.child_by_field_name("function") else {
+            return;
+        };
+        let Some(arguments_node) = node.child_by_field_name("arguments") else {
+            return;
+        };
+        let function = match function_node.kind() {
+            "symbol" => self.resolver.symbol_item(function_node),
+            "field_access" | "scope_access" => {
+                let function = self.resolver.member_item(function_node);
+                if function.is_none() {
+                    self.check_method_exists(function_node);
+                }
+                function
+            }
+            _ => None,
+        };
+        // The calls to unknown functions are reported by the diagnostics of the undefined
+        // symbols, there is nothing to check them against here.
+        let Some(function) = function else {
+            return;
+        };
+        // Release the lock before resolving the types of the arguments.
+        let function_item = match &*function.read().unwrap() {
+            SPItem::Function(function_item) => function_item.clone(),
+            _ => return,
+        };
+
+        let mut cursor = arguments_node.walk();
+        let arguments: Vec<Node> = arguments_node
+            .named_children(&mut cursor)
+            .filter(|argument| argument.kind() != "comment")
+            .collect();
+        self.check_arguments_count(&function_item, function_node, &arguments);
+
+        // Named arguments can be passed in any order, only check the positional ones.
+        for (argument, param) in arguments
+            .iter()
+            .take_while(|argument| !argument.kind().contains("named"))
+            .zip(function_item.params.iter())
+        {
+            let param = param.read().unwrap();
+            // A parameter without a tag accepts any cell, don't guess.
+            let expects_int = param
+                .type_
+                .as_ref()
+                .map_or(false, |type_| type_.name == "int");
+            if !expects_int {
+                continue;
+            }
+            if let Some(ExpressionType::Value(name)) = self.resolver.expression_type(*argument) {
+                if name == "float" || name == "Float" {
+                    self.push(
+                        ts_range_to_lsp_range(&argument.range()),
+                        format!(
+                            "Tag mismatch: `{}` passed to the `int` parameter `{}`.",
+                            name, param.name
+                        ),
+                    );
+                }
+            }
+        }
+    }
+
+    /// Check that a call passes at least as many arguments as the parameters without a default
+    /// value, and no more arguments than the parameters unless the function is variadic.
+    fn check_arguments_count(
+        &mut self,
+        function_item: &FunctionItem,
+        function_node: Node,
+        arguments: &[Node],
+    ) {
+        let params_count = function_item.params.len();
+        let required_count = function_item
+            .params
+            .iter()
+            .filter(|param| !param.read().unwrap().has_default)
+            .count();
+        let has_named_arguments = arguments
+            .iter()
+            .any(|argument| argument.kind().contains("named"));
+        let (range, expected) = if arguments.len() < required_count && !has_named_arguments {
+            let expected = if function_item.is_variadic || required_count != params_count {
+                format!("at least {}", plural_arguments(required_count))
+            } else {
+                plural_arguments(required_count)
+            };
+            (ts_range_to_lsp_range(&function_node.range()), expected)
+        } else if arguments.len() > params_count && !function_item.is_variadic {
+            let expected = if required_count != params_count {
+                format!("at most {}", plural_arguments(params_count))
+            } else {
+                plural_arguments(params_count)
+            };
+            // Only highlight the extra arguments.
+            let range = Range {
+                start: ts_range_to_lsp_range(&arguments[params_count].range()).start,
+                end: ts_range_to_lsp_range(&arguments[arguments.len() - 1].range()).end,
+            };
+            (range, expected)
+        } else {
+            return;
+        };
+        self.push(
+            range,
+            format!(
+                "`{}` expects {}, got {}.",
+                function_item.name,
+                expected,
+                arguments.len()
+            ),
+        );
+    }
+
+    /// Check that the method called by a member access exists, when the target of the access is
+    /// a methodmap whose members are all known.
+    fn check_method_exists(&mut self, node: Node) {
+        let Some((target_node, field_node)) = member_access_nodes(node) else {
+            return;
+        };
+        let Some(target_type) = self.resolver.expression_type(target_node) else {
+            return;
+        };
+        if !self.resolver.is_known_methodmap(&target_type) {
+            return;
+        }
+        let Ok(field) = field_node.utf8_text(self.document.preprocessed_text.as_bytes()) else {
+            return;
+        };
+        self.push(
+            ts_range_to_lsp_range(&field_node.range()),
+            format!(
+                "Method `{}` does not exist on methodmap `{}`.",
+                field,
+                target_type.name()
+            ),
+        );
+    }
+
+    /// Check that the target of an assignment, or of an increment, is not a const parameter.
+    fn check_assignment(&mut self, node: Node) {
+        // Assigning to an element of a const array is forbidden as well.
+        let mut symbol_node = node;
+        while matches!(
+            symbol_node.kind(),
+            "array_indexed_access" | "parenthesized_expression"
+        ) {
+            symbol_node = match symbol_node
+                .child_by_field_name("array")
+                .or_else(|| symbol_node.named_child(0))
+            {
+                Some(child) => child,
+                None => return,
+            };
+        }
+        if symbol_node.kind() != "symbol" {
+            return;
+        }
+        let Some(item) = self.resolver.symbol_item(symbol_node) else {
+            return;
+        };
+        let item = item.read().unwrap();
+        let SPItem::Variable(variable_item) = &*item else {
+            return;
+        };
+        if !variable_item
+            .storage_class
+            .contains(&VariableStorageClass::Const)
+        {
+            return;
+        }
+        let Some(parent) = variable_item
+            .parent
+            .as_ref()
+            .and_then(|parent| parent.upgrade())
+        else {
+            return;
+        };
+        let is_parameter = match &*parent.read().unwrap() {
+            SPItem::Function(function_item) => function_item
+                .params
+                .iter()
+                .any(|param| param.read().unwrap().name == variable_item.name),
+            _ => false,
+        };
+        if is_parameter {
+            self.push(
+                ts_range_to_lsp_range(&symbol_node.range()),
+                format!(
+                    "Cannot assign to the const parameter `{}`.",
+                    variable_item.name
+                ),
+            );
+        }
+    }
+
+    fn push(&mut self, range: Range, message: String) {
+        self.diagnostics.push(Diagnostic {
+            range,
+            message,
+            severity: Some(DiagnosticSeverity::WARNING),
+            ..Default::default()
+        });
+    }
+}
+
+/// Return a number of arguments followed by the word argument, e.g `1 argument`.
+fn plural_arguments(count: usize) -> String {
+    if count == 1 {
+        "1 argument".to_string()
+    } else {
+        format!("{} arguments", count)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::tests::fixtures::TestStore;
+
+    /// Return the messages of the checker for a single document.
+    fn check(text: &str) -> Vec<String> {
+        let test_store = TestStore::new(&format!("//! main.sp\n{}", text)).unwrap();
+        let uri = test_store.uri("main.sp");
+        let document = test_store.store.documents.get(&uri).unwrap();
+        let mut messages: Vec<String> = document
+            .diagnostics
+            .checker_diagnostics
+            .iter()
+            .map(|diagnostic| diagnostic.message.clone())
+            .collect();
+        messages.sort();
+
+        messages
+    }
+
+    #[test]
+    fn too_few_arguments() {
+        assert_eq!(
+            check("void foo(int a, int b) {}\nvoid bar() { foo(1); }"),
+            vec!["`foo` expects 2 arguments, got 1."]
+        );
+    }
+
+    #[test]
+    fn too_many_arguments() {
+        assert_eq!(
+            check("void foo(int a) {}\nvoid bar() { foo(1, 2, 3); }"),
+            vec!["`foo` expects 1 argument, got 3."]
+        );
+    }
+
+    #[test]
+    fn variadic_arguments() {
+        assert!(check("void foo(int a, any ...) {}\nvoid bar() { foo(1, 2, 3); }").is_empty());
+        assert_eq!(
+            check("void foo(int a, any ...) {}\nvoid bar() { foo(); }"),
+            vec!["`foo` expects at least 1 argument, got 0."]
+        );
+    }
+
+    #[test]
+    fn default_arguments() {
+        assert!(check("void foo(int a, int b = 1) {}\nvoid bar() { foo(1); }").is_empty());
+        assert_eq!(
+            check("void foo(int a, int b = 1) {}\nvoid bar() { foo(1, 2, 3); }"),
+            vec!["`foo` expects at most 2 arguments, got 3."]
+        );
+    }
+
+    #[test]
+    fn named_arguments() {
+        assert!(check("void foo(int a, int b) {}\nvoid bar() { foo(.b = 1, .a = 2); }").is_empty());
+    }
+
+    #[test]
+    fn float_to_int_argument() {
+        assert_eq!(
+            check("void foo(int a) {}\nvoid bar() { float f; foo(f); }"),
+            vec!["Tag mismatch: `float` passed to the `int` parameter `a`."]
+        );
+    }
+
+    #[test]
+    fn float_to_untagged_argument() {
+        assert!(check("void foo(a) {}\nvoid bar() { float f; foo(f); }").is_empty());
+    }
+
+    #[test]
+    fn const_parameter_assignment() {
+        assert_eq!(
+            check("void foo(const int a) { a = 1; }"),
+            vec!["Cannot assign to the const parameter `a`."]
+        );
+        assert!(check("void foo(int a) { a = 1; }").is_empty());
+    }
+
+    #[test]
+    fn unknown_method() {
+        assert_eq!(
+            check(
+                "methodmap Foo { public void Bar() {} }\nvoid baz() { Foo foo; foo.Bar(); foo.Qux(); }"
+            ),
+            vec!["Method `Qux` does not exist on methodmap `Foo`."]
+        );
+    }
+
+    #[test]
+    fn unknown_function() {
+        assert!(check("void foo() { bar(1); }").is_empty());
+    }
+}
@@ -12,6 +12,13 @@ pub struct DocumentDiagnostics {
 
     /// Diagnostics that depend on the includes of the document, such as unresolved items.
     pub(crate) global_diagnostics: Vec<Diagnostic>,
+
+    /// Diagnostics of the [checker](super::checker::Checker), computed when the references of
+    /// the document are resolved.
+    pub(crate) checker_diagnostics: Vec<Diagnostic>,
+
+    /// Diagnostics of the unused items, such as unused local variables or includes.
+    pub(crate) unused_diagnostics: Vec<Diagnostic>,
 }
 
 impl DocumentDiagnostics {
@@ -23,6 +30,8 @@ impl DocumentDiagnostics {
             .map(|diagnostic| diagnostic.to_lsp_diagnostic())
             .collect();
         lsp_diagnostics.extend(self.global_diagnostics.clone());
+        lsp_diagnostics.extend(self.checker_diagnostics.clone());
+        lsp_diagnostics.extend(self.unused_diagnostics.clone());
         if !disable_local_diagnostics {
             lsp_diagnostics.extend(self.local_diagnostics.clone());
         }
@@ -1,14 +1,32 @@
 use std::sync::{Arc, RwLock};
 
-use lsp_types::{Diagnostic, DiagnosticSeverity, DiagnosticTag};
+use fxhash::{FxHashMap, FxHashSet};
+use lsp_types::{Diagnostic, DiagnosticSeverity, DiagnosticTag, NumberOrString, Range, Url};
 use tree_sitter::{Node, QueryCursor};
 
-use crate::{document::Document, spitem::SPItem, store::Store, utils::ts_range_to_lsp_range};
+use crate::{
+    document::Document,
+    spitem::{
+        function_item::{FunctionDefinitionType, FunctionItem, FunctionVisibility},
+        include_item::IncludeItem,
+        variable_item::VariableItem,
+        Location, SPItem,
+    },
+    store::Store,
+    utils::{edit_distance, ts_range_to_lsp_range},
+};
 
 use super::parser::ERROR_QUERY;
+pub(crate) mod checker;
 pub(crate) mod document_diagnostics;
 pub(crate) mod spcomp;
 
+/// Code of the diagnostics of the undefined symbols, whose `data` holds the suggested names.
+pub(crate) const UNDEFINED_SYMBOL_CODE: &str = "undefined-symbol";
+
+/// Maximum number of names suggested for an undefined symbol.
+const MAX_SUGGESTIONS: usize = 3;
+
 impl Store {
     /// Clear all diagnostics from the documents in the store.
     pub(super) fn clear_all_diagnostics(&mut self) {
@@ -18,23 +36,74 @@ impl Store {
         }
     }
 
-    /// Clear all global non spcomp diagnostics from the documents in the store.
-    pub(super) fn clear_all_global_diagnostics(&mut self) {
-        for document in self.documents.values_mut() {
-            document.diagnostics.global_diagnostics.clear();
+    /// Clear the global non spcomp diagnostics of some documents.
+    ///
+    /// # Arguments
+    ///
+    /// * `uris` - [Uris](Url) of the documents to clear.
+    pub(super) fn clear_global_diagnostics(&mut self, uris: &FxHashSet<Arc<Url>>) {
+        for uri in uris {
+            if let Some(document) = self.documents.get_mut(uri) {
+                document.diagnostics.global_diagnostics.clear();
+            }
         }
     }
 
-    /// Lint all documents for the use of deprecated items.
+    /// Return the documents whose diagnostics may change when a document changes: the document
+    /// itself, and the documents outside of the include directories which share an including
+    /// document with it, e.g the other files of its plugin.
+    ///
+    /// # Arguments
+    ///
+    /// * `uri` - [Uri](Url) of the changed document.
+    pub(super) fn related_documents(&self, uri: &Arc<Url>) -> FxHashSet<Arc<Url>> {
+        // Walk up the documents which include the changed one, directly or not.
+        let mut including_uris = FxHashSet::default();
+        including_uris.insert(uri.clone());
+        let mut added = true;
+        while added {
+            added = false;
+            for document in self.documents.values() {
+                if !including_uris.contains(&document.uri)
+                    && document
+                        .includes
+                        .keys()
+                        .any(|include_uri| including_uris.contains(include_uri))
+                {
+                    including_uris.insert(document.uri.clone());
+                    added = true;
+                }
+            }
+        }
+        let mut includes = FxHashSet::default();
+        for including_uri in including_uris.iter() {
+            if let Some(document) = self.documents.get(including_uri) {
+                self.get_included_files(document, &mut includes);
+            }
+        }
+        let mut uris = including_uris;
+        uris.extend(includes.into_iter().map(Arc::new));
+        uris.retain(|other_uri| other_uri == uri || !self.is_in_includes_directories(other_uri));
+
+        uris
+    }
+
+    /// Lint documents for the use of deprecated items.
     ///
     /// # Arguments
     ///
     /// * `all_items_flat` - Vector of all the [SPItems](SPItem) that are in the mainpath's scope.
-    pub(super) fn get_deprecated_diagnostics(&mut self, all_items_flat: &[Arc<RwLock<SPItem>>]) {
+    /// * `uris` - [Uris](Url) of the documents to lint.
+    pub(super) fn get_deprecated_diagnostics(
+        &mut self,
+        all_items_flat: &[Arc<RwLock<SPItem>>],
+        uris: &FxHashSet<Arc<Url>>,
+    ) {
         for item in all_items_flat.iter() {
             if let Some(description) = item.read().unwrap().description() {
                 if let Some(deprecated) = description.deprecated {
-                    if !&item.read().unwrap().uri().as_str().ends_with(".inc") {
+                    let uri = item.read().unwrap().uri();
+                    if !uri.as_str().ends_with(".inc") && uris.contains(&uri) {
                         if let Some(document) = self.documents.get_mut(&item.read().unwrap().uri())
                         {
                             document.diagnostics.local_diagnostics.push(Diagnostic {
@@ -48,7 +117,9 @@ impl Store {
                     }
                     if let Some(references) = item.read().unwrap().references() {
                         for reference in references.iter() {
-                            if reference.uri.as_str().ends_with(".inc") {
+                            if reference.uri.as_str().ends_with(".inc")
+                                || !uris.contains(&reference.uri)
+                            {
                                 continue;
                             }
                             if let Some(document) = self.documents.get_mut(&reference.uri) {
@@ -68,7 +139,198 @@ impl Store {
     }
 }
 
+impl Store {
+    /// Lint documents outside of the include directories for the local variables, parameters,
+    /// functions and includes which are never used, depending on the
+    /// [options](crate::options::UnusedDiagnosticsOptions). The previous unused diagnostics of
+    /// the documents are replaced.
+    ///
+    /// # Arguments
+    ///
+    /// * `uris` - [Uris](Url) of the documents to lint.
+    pub(super) fn get_unused_diagnostics(&mut self, uris: &FxHashSet<Arc<Url>>) {
+        let options = self.environment.options.unused_diagnostics.clone();
+        let mut diagnostics: FxHashMap<Arc<Url>, Vec<Diagnostic>> = FxHashMap::default();
+        for uri in uris {
+            let document_diagnostics = diagnostics.entry(uri.clone()).or_default();
+            let Some(document) = self.documents.get(uri) else {
+                continue;
+            };
+            if !document.parsed || self.is_in_includes_directories(&document.uri) {
+                continue;
+            }
+            for item in document.get_sp_items_flat() {
+                match &*item.read().unwrap() {
+                    SPItem::Variable(variable_item) if options.variables => {
+                        if let Some(kind) = unused_variable_kind(variable_item) {
+                            document_diagnostics.push(unused_diagnostic(
+                                variable_item.v_range,
+                                format!("Unused {} `{}`.", kind, variable_item.name),
+                            ));
+                        }
+                    }
+                    SPItem::Function(function_item) if options.functions => {
+                        if !document.uri.as_str().ends_with(".inc")
+                            && is_unused_function(function_item)
+                        {
+                            document_diagnostics.push(unused_diagnostic(
+                                function_item.v_range,
+                                format!("Unused function `{}`.", function_item.name),
+                            ));
+                        }
+                    }
+                    _ => (),
+                }
+            }
+            if options.includes {
+                for include_item in self.get_unused_includes(&document.uri) {
+                    document_diagnostics.push(unused_diagnostic(
+                        include_item.v_range,
+                        format!("Unused include `{}`.", include_item.name),
+                    ));
+                }
+            }
+        }
+        for (uri, diagnostics) in diagnostics {
+            if let Some(document) = self.documents.get_mut(&uri) {
+                document.diagnostics.unused_diagnostics = diagnostics;
+            }
+        }
+    }
+
+    /// Lint documents outside of the include directories for the tokens which could not be
+    /// resolved, and suggest the closest names of the items in the scope of their main path.
+    ///
+    /// # Arguments
+    ///
+    /// * `uris` - [Uris](Url) of the documents to lint.
+    pub(super) fn get_undefined_symbol_diagnostics(&mut self, uris: &FxHashSet<Arc<Url>>) {
+        let mut names_of_main: FxHashMap<Option<Url>, Vec<String>> = FxHashMap::default();
+        let mut diagnostics: FxHashMap<Arc<Url>, Vec<Diagnostic>> = FxHashMap::default();
+        for document in uris.iter().filter_map(|uri| self.documents.get(uri)) {
+            if !document.parsed
+                || document.unresolved_tokens.is_empty()
+                || self.is_in_includes_directories(&document.uri)
+            {
+                continue;
+            }
+            let main_uri = self.main_uri_of(&document.uri);
+            let names = names_of_main.entry(main_uri.clone()).or_insert_with(|| {
+                let mut names: Vec<String> = self
+                    .get_all_items_of_main(main_uri, true)
+                    .0
+                    .iter()
+                    .filter_map(|item| match &*item.read().unwrap() {
+                        SPItem::Include(_) => None,
+                        item => Some(item.name()),
+                    })
+                    .collect();
+                names.sort();
+                names.dedup();
+                names
+            });
+            let document_diagnostics = diagnostics.entry(document.uri.clone()).or_default();
+            for (text, ranges) in document.unresolved_tokens.iter() {
+                let suggestions = closest_names(text, names);
+                let message = match suggestions.first() {
+                    Some(suggestion) => format!(
+                        "Undefined symbol `{}`. Did you mean `{}`?",
+                        text, suggestion
+                    ),
+                    None => format!("Undefined symbol `{}`.", text),
+                };
+                for range in ranges.iter() {
+                    document_diagnostics.push(Diagnostic {
+                        range: *range,
+                        message: message.clone(),
+                        // The resolution is not as thorough as the compiler's, don't flag the
+                        // symbols as errors or warnings.
+                        severity: Some(DiagnosticSeverity::INFORMATION),
+                        code: Some(NumberOrString::String(UNDEFINED_SYMBOL_CODE.to_string())),
+                        data: Some(serde_json::Value::from(suggestions.clone())),
+                        ..Default::default()
+                    });
+                }
+            }
+        }
+        for (uri, diagnostics) in diagnostics {
+            if let Some(document) = self.documents.get_mut(&uri) {
+                document.diagnostics.global_diagnostics.extend(diagnostics);
+            }
+        }
+    }
+
+    /// Return the includes of a document which do not provide any item used in that document,
+    /// directly or through their own includes.
+    ///
+    /// Include files are never considered, as their includes may be used by the files which
+    /// include them.
+    ///
+    /// # Arguments
+    ///
+    /// * `uri` - [Uri](Url) of the document to check.
+    pub(crate) fn get_unused_includes(&self, uri: &Url) -> Vec<IncludeItem> {
+        let mut unused_includes = vec![];
+        let Some(document) = self.documents.get(uri) else {
+            return unused_includes;
+        };
+        if uri.as_str().ends_with(".inc") {
+            return unused_includes;
+        }
+        let macro_symbols: FxHashSet<&str> = document
+            .macro_symbols
+            .iter()
+            .map(|symbol| symbol.text.as_str())
+            .collect();
+        for item in document.sp_items.iter() {
+            let SPItem::Include(include_item) = &*item.read().unwrap() else {
+                continue;
+            };
+            let mut includes = FxHashSet::default();
+            includes.insert(include_item.include_uri.as_ref().clone());
+            if let Some(include_document) = self.documents.get(&include_item.include_uri) {
+                self.get_included_files(include_document, &mut includes);
+            }
+            let used = includes
+                .iter()
+                .filter_map(|include_uri| self.documents.get(include_uri))
+                .flat_map(|include_document| include_document.get_sp_items_flat())
+                .any(|child| {
+                    let child = child.read().unwrap();
+                    if matches!(&*child, SPItem::Define(_))
+                        && macro_symbols.contains(child.name().as_str())
+                    {
+                        return true;
+                    }
+                    child.references().map_or(false, |references| {
+                        references.iter().any(|reference| *reference.uri == *uri)
+                    })
+                });
+            if !used {
+                unused_includes.push(include_item.clone());
+            }
+        }
+
+        unused_includes
+    }
+}
+
 impl Document {
+    /// Return the hints of the code disabled by the preprocessor, so that the client can grey it
+    /// out. They are not tied to the syntax linter and are always published.
+    pub(crate) fn get_inactive_code_diagnostics(&self) -> Vec<Diagnostic> {
+        self.inactive_ranges
+            .iter()
+            .map(|range| Diagnostic {
+                range: *range,
+                message: "Code disabled by the preprocessor.".to_string(),
+                severity: Some(DiagnosticSeverity::HINT),
+                tags: Some(vec![DiagnosticTag::UNNECESSARY]),
+                ..Default::default()
+            })
+            .collect()
+    }
+
     /// Capture all the syntax errors of a document and add them to its Local Diagnostics.
     /// Overrides all previous Local Diagnostics.
     ///
@@ -100,3 +362,182 @@ impl Document {
         // TODO: Add MISSING query here once https://github.com/tree-sitter/tree-sitter/issues/606 is fixed.
     }
 }
+
+/// Return `variable` or `parameter` if a local variable or a parameter is never referenced, [None]
+/// otherwise. The parameters of public functions, forwards and natives are never reported, as
+/// their signature is imposed.
+///
+/// # Arguments
+///
+/// * `variable_item` - [VariableItem] to check.
+fn unused_variable_kind(variable_item: &VariableItem) -> Option<&'static str> {
+    let parent = variable_item.parent.as_ref()?.upgrade()?;
+    let SPItem::Function(function_item) = &*parent.read().unwrap() else {
+        return None;
+    };
+    let is_parameter = function_item
+        .params
+        .iter()
+        .any(|param| param.read().unwrap().name == variable_item.name);
+    if is_parameter
+        && (function_item.is_public()
+            || function_item.definition_type != FunctionDefinitionType::None)
+    {
+        return None;
+    }
+    if is_referenced(
+        &variable_item.references,
+        &variable_item.uri,
+        &variable_item.range,
+    ) {
+        return None;
+    }
+
+    Some(if is_parameter {
+        "parameter"
+    } else {
+        "variable"
+    })
+}
+
+/// Check if a function is a non public `stock` or `static` function which is never called.
+///
+/// # Arguments
+///
+/// * `function_item` - [FunctionItem] to check.
+fn is_unused_function(function_item: &FunctionItem) -> bool {
+    function_item.parent.is_none()
+        && !function_item.is_public()
+        && (function_item.is_static()
+            || function_item
+                .visibility
+                .contains(&FunctionVisibility::Stock))
+        && function_item.definition_type == FunctionDefinitionType::None
+        && !is_referenced(
+            &function_item.references,
+            &function_item.uri,
+            &function_item.range,
+        )
+}
+
+/// Check if an item has a reference other than its own declaration.
+///
+/// # Arguments
+///
+/// * `references` - [References](Location) of the item.
+/// * `uri` - [Uri](Url) of the file where the item is declared.
+/// * `range` - [Range] of the name of the item.
+fn is_referenced(references: &[Location], uri: &Url, range: &Range) -> bool {
+    references
+        .iter()
+        .any(|reference| *reference.uri != *uri || reference.range != *range)
+}
+
+/// Return the names which are the closest to an undefined symbol, ignoring the case, from the
+/// closest to the furthest. Names which need more edits than a third of the length of the symbol
+/// are not suggested.
+///
+/// # Arguments
+///
+/// * `symbol` - Text of the undefined symbol.
+/// * `names` - Names of the known items.
+fn closest_names(symbol: &str, names: &[String]) -> Vec<String> {
+    let lowercase_symbol = symbol.to_lowercase();
+    let symbol_length = symbol.chars().count();
+    let max_distance = (symbol_length / 3).max(1);
+    let mut candidates: Vec<(usize, &String)> = names
+        .iter()
+        .filter(|name| {
+            name.as_str() != symbol && name.chars().count().abs_diff(symbol_length) <= max_distance
+        })
+        .map(|name| (edit_distance(&lowercase_symbol, &name.to_lowercase()), name))
+        .filter(|(distance, _)| *distance <= max_distance)
+        .collect();
+    candidates.sort();
+
+    candidates
+        .into_iter()
+        .take(MAX_SUGGESTIONS)
+        .map(|(_, name)| name.clone())
+        .collect()
+}
+
+/// Build a hint for some code which is never used.
+///
+/// # Arguments
+///
+/// * `range` - [Range] of the code.
+/// * `message` - Message of the diagnostic.
+fn unused_diagnostic(range: Range, message: String) -> Diagnostic {
+    Diagnostic {
+        range,
+        message,
+        severity: Some(DiagnosticSeverity::HINT),
+        tags: Some(vec![DiagnosticTag::UNNECESSARY]),
+        ..Default::default()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use fxhash::FxHashSet;
+
+    use crate::tests::fixtures::TestStore;
+
+    /// Return the messages of the undefined symbol diagnostics of a single document.
+    fn undefined_symbols(text: &str) -> Vec<String> {
+        let mut test_store = TestStore::new(&format!("//! main.sp\n{}", text)).unwrap();
+        let uri = test_store.uri("main.sp");
+        let mut uris = FxHashSet::default();
+        uris.insert(uri.clone());
+        test_store.store.get_undefined_symbol_diagnostics(&uris);
+
+        test_store.store.documents[&uri]
+            .diagnostics
+            .global_diagnostics
+            .iter()
+            .map(|diagnostic| diagnostic.message.clone())
+            .collect()
+    }
+
+    #[test]
+    fn undefined_symbols_are_reported() {
+        let messages = undefined_symbols("void foo() { int a = bar; }");
+        assert_eq!(messages.len(), 1);
+        assert!(messages[0].starts_with("Undefined symbol `bar`."));
+    }
+
+    #[test]
+    fn macros_are_not_undefined_symbols() {
+        let messages = undefined_symbols(
+            r#"#define MAX_PLAYERS 65
+#define SQUARE(%1) ((%1) * (%1))
+void foo() { int a = MAX_PLAYERS; int b = SQUARE(a); }"#,
+        );
+        assert!(messages.is_empty(), "{:?}", messages);
+    }
+
+    #[test]
+    fn enum_struct_members_are_not_undefined_symbols() {
+        let messages = undefined_symbols(
+            r#"enum struct Point
+{
+    int x;
+    int y;
+
+    int Sum()
+    {
+        return this.x + this.y;
+    }
+}
+
+void foo()
+{
+    Point p;
+    p.x = 1;
+    int a = p.Sum() + p.y;
+}"#,
+        );
+        assert!(messages.is_empty(), "{:?}", messages);
+    }
+}
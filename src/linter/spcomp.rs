@@ -1,12 +1,26 @@
-use std::{env, fs, path::PathBuf, process::Command};
+use std::{
+    env, fs,
+    io::Read,
+    path::PathBuf,
+    process::{Command, Stdio},
+    sync::atomic::{AtomicU64, Ordering},
+    thread::{self, JoinHandle},
+    time::Duration,
+};
 
 use anyhow::{anyhow, Context};
 use fxhash::FxHashMap;
 use lazy_static::lazy_static;
-use lsp_types::{Diagnostic, DiagnosticSeverity, Position, Range, Url};
+use lsp_types::{
+    CodeDescription, Diagnostic, DiagnosticSeverity, NumberOrString, Position, Range, Url,
+};
 use regex::{Captures, Regex};
 
-use crate::store::Store;
+use crate::{document::SPToken, store::Store};
+
+/// Id of the next spcomp run. The ids are unique across files, so that a run can't be mistaken
+/// for a later run of a file which was closed meanwhile.
+static NEXT_SPCOMP_RUN_ID: AtomicU64 = AtomicU64::new(1);
 
 /// Severity levels of spcomp errors.
 #[derive(Debug, Clone)]
@@ -27,59 +41,138 @@ impl SPCompSeverity {
     }
 }
 
+/// Link to the list of the spcomp error messages, used as the description of the error codes.
+const SPCOMP_MESSAGES_URL: &str =
+    "https://github.com/alliedmodders/sourcepawn/blob/master/compiler/messages.h";
+
 /// Representation of an spcomp error.
 #[derive(Debug, Clone)]
 pub(crate) struct SPCompDiagnostic {
     /// [Uri](Url) of the document where the error comes from.
     uri: Url,
 
-    /// Line index of the error.
-    line_index: u32,
+    /// Range of the error. Spans the whole line unless it could be narrowed to a symbol.
+    range: Range,
 
     /// Severity of the error.
     severity: SPCompSeverity,
 
+    /// Numeric code of the error, i.e `17` for `error 017`.
+    code: Option<i32>,
+
     /// Message of the error.
     message: String,
 }
 
 impl SPCompDiagnostic {
     pub(crate) fn from_spcomp_captures(captures: Captures) -> Option<Self> {
+        let line_index = captures.get(2)?.as_str().parse::<u32>().ok()? - 1;
         Some(Self {
             uri: Url::from_file_path(captures.get(1)?.as_str()).ok()?,
-            line_index: captures.get(2)?.as_str().parse::<u32>().ok()? - 1,
+            range: Range {
+                start: Position {
+                    line: line_index,
+                    character: 0,
+                },
+                end: Position {
+                    line: line_index,
+                    character: 1000,
+                },
+            },
             severity: match captures.get(4)?.as_str() {
                 "warning" => SPCompSeverity::Warning,
                 "error" => SPCompSeverity::Error,
                 "fatal error" => SPCompSeverity::FatalError,
                 _ => todo!(),
             },
+            code: captures
+                .get(5)
+                .and_then(|code| code.as_str().parse::<i32>().ok()),
             message: captures.get(6)?.as_str().to_string(),
         })
     }
 
+    /// Return the name of the symbol the message of the error refers to, if any.
+    /// spcomp quotes it, i.e `undefined symbol "foo"`.
+    fn symbol_name(&self) -> Option<&str> {
+        lazy_static! {
+            static ref RE: Regex =
+                Regex::new(r#""(\w+)""#).expect("Failed to compile spcomp symbol regex.");
+        }
+        Some(RE.captures(&self.message)?.get(1)?.as_str())
+    }
+
+    /// Narrow the range of the error to the first token of its line that matches the symbol
+    /// named in its message.
+    ///
+    /// # Arguments
+    ///
+    /// * `tokens` - [Tokens](SPToken) of the document of the error.
+    fn narrow_range(&mut self, tokens: &[SPToken]) {
+        let name = match self.symbol_name() {
+            Some(name) => name.to_string(),
+            None => return,
+        };
+        let line = self.range.start.line;
+        let range = tokens
+            .iter()
+            .flat_map(|token| match token {
+                SPToken::Symbol(token) => vec![token],
+                SPToken::Method((parent, field)) => vec![parent, field],
+            })
+            .filter(|token| token.range.start.line == line && token.text == name)
+            .map(|token| token.range)
+            .min_by_key(|range| range.start.character);
+        if let Some(range) = range {
+            self.range = range;
+        }
+    }
+
     /// Convert to an [LSP Diagnostic](lsp_types::Diagnostic).
     pub(crate) fn to_lsp_diagnostic(&self) -> Diagnostic {
         Diagnostic {
-            range: Range {
-                start: Position {
-                    line: self.line_index,
-                    character: 0,
-                },
-                end: Position {
-                    line: self.line_index,
-                    character: 1000,
-                },
-            },
+            range: self.range,
             message: self.message.clone(),
             severity: Some(self.severity.to_lsp_severity()),
+            code: self.code.map(NumberOrString::Number),
+            code_description: self.code.and_then(|_| {
+                Some(CodeDescription {
+                    href: Url::parse(SPCOMP_MESSAGES_URL).ok()?,
+                })
+            }),
+            source: Some("spcomp".to_string()),
             ..Default::default()
         }
     }
 }
 
 impl Store {
+    /// Register a new spcomp run for a file, which cancels the runs requested before for the
+    /// same file. Return a closure which tells whether the run has been cancelled since.
+    ///
+    /// # Arguments
+    ///
+    /// * `uri` - [Uri](Url) of the file to compile.
+    pub(crate) fn start_spcomp_run(&self, uri: &Url) -> impl Fn() -> bool + Send + 'static {
+        let run_id = NEXT_SPCOMP_RUN_ID.fetch_add(1, Ordering::SeqCst);
+        self.spcomp_runs.lock().unwrap().insert(uri.clone(), run_id);
+        let spcomp_runs = self.spcomp_runs.clone();
+        let uri = uri.clone();
+
+        move || spcomp_runs.lock().unwrap().get(&uri) != Some(&run_id)
+    }
+
+    /// Cancel the spcomp runs of a file and forget about it.
+    ///
+    /// # Arguments
+    ///
+    /// * `uri` - [Uri](Url) of the file.
+    pub(crate) fn cancel_spcomp_runs(&self, uri: &Url) {
+        self.spcomp_runs.lock().unwrap().remove(uri);
+    }
+
     /// Run spcomp and extract the potential errors from its output.
+    /// Return [None] if the run was cancelled before spcomp exited.
     ///
     /// # Errors
     ///
@@ -88,11 +181,13 @@ impl Store {
     /// # Arguments
     ///
     /// * `uri` - [Uri](Url) of the file to compile.
+    /// * `is_cancelled` - Polled while spcomp is running, kills spcomp when it returns `true`.
     pub(crate) fn get_spcomp_diagnostics(
         &mut self,
         uri: Url,
-    ) -> anyhow::Result<FxHashMap<Url, Vec<SPCompDiagnostic>>> {
-        let output = Command::new(
+        is_cancelled: impl Fn() -> bool,
+    ) -> anyhow::Result<Option<FxHashMap<Url, Vec<SPCompDiagnostic>>>> {
+        let child = Command::new(
             self.environment
                 .options
                 .spcomp_path
@@ -100,14 +195,40 @@ impl Store {
                 .context("Failed to convert spcomp path to string.")?,
         )
         .args(self.build_args(&uri)?)
-        .output();
-        let out_path = self.get_out_path();
+        .stdout(Stdio::piped())
+        .stderr(Stdio::piped())
+        .spawn();
+        let output = child.and_then(|mut child| {
+            let stdout = spawn_pipe_reader(child.stdout.take());
+            let stderr = spawn_pipe_reader(child.stderr.take());
+            loop {
+                if child.try_wait()?.is_some() {
+                    break;
+                }
+                if is_cancelled() {
+                    child.kill()?;
+                    child.wait()?;
+                    return Ok(None);
+                }
+                thread::sleep(Duration::from_millis(50));
+            }
+            Ok(Some((
+                stdout.join().unwrap_or_default(),
+                stderr.join().unwrap_or_default(),
+            )))
+        });
+        let out_path = self.get_out_path(&uri);
         if out_path.exists() {
             let _ = fs::remove_file(out_path);
         }
 
-        let output = output?;
-        let stderr = String::from_utf8_lossy(&output.stderr);
+        let (stdout, stderr) = match output? {
+            Some(output) => output,
+            None => {
+                log::trace!("Cancelled spcomp run for {:?}", uri);
+                return Ok(None);
+            }
+        };
         if !stderr.is_empty() {
             return Err(anyhow::anyhow!(
                 "Failed to run spcomp with error: {}",
@@ -115,7 +236,6 @@ impl Store {
             ));
         }
 
-        let stdout = String::from_utf8_lossy(&output.stdout);
         let mut res: FxHashMap<Url, Vec<SPCompDiagnostic>> = FxHashMap::default();
         for diagnostic in parse_spcomp_errors(&stdout) {
             if let Some(diagnostics) = res.get_mut(&diagnostic.uri) {
@@ -125,7 +245,7 @@ impl Store {
             }
         }
 
-        Ok(res)
+        Ok(Some(res))
     }
 
     /// Return a [vector](Vec) of [strings](String) of the arguments to run spcomp.
@@ -164,7 +284,7 @@ impl Store {
             }
         }
 
-        if let Some(out_path_str) = self.get_out_path().to_str() {
+        if let Some(out_path_str) = self.get_out_path(uri).to_str() {
             args.push(format!("-o{}", out_path_str));
         }
         args.push("--syntax-only".to_string());
@@ -175,8 +295,17 @@ impl Store {
     }
 
     /// Generate a temporary path for the output of spcomp. This is not needed with the `--syntax-only` switch.
-    fn get_out_path(&self) -> PathBuf {
-        env::temp_dir().join(format!("{}.smx", self.environment.sp_comp_uuid))
+    /// The path is unique for each compiled file, so that concurrent runs don't share it.
+    ///
+    /// # Arguments
+    ///
+    /// * `uri` - [Uri](Url) of the file to compile.
+    fn get_out_path(&self, uri: &Url) -> PathBuf {
+        env::temp_dir().join(format!(
+            "{}-{:x}.smx",
+            self.environment.sp_comp_uuid,
+            fxhash::hash64(uri.as_str())
+        ))
     }
 
     /// Ingest a map of spcomp_diganostics into the [Store].
@@ -184,14 +313,34 @@ impl Store {
         &mut self,
         spcomp_diagnostics_map: FxHashMap<Url, Vec<SPCompDiagnostic>>,
     ) {
-        for (uri, diagnostics) in spcomp_diagnostics_map.iter() {
-            if let Some(document) = self.documents.get_mut(uri) {
-                document.diagnostics.sp_comp_diagnostics = (*diagnostics).clone();
+        for (uri, diagnostics) in spcomp_diagnostics_map.into_iter() {
+            if let Some(document) = self.documents.get_mut(&uri) {
+                let mut diagnostics = diagnostics;
+                for diagnostic in diagnostics.iter_mut() {
+                    diagnostic.narrow_range(&document.tokens);
+                }
+                document.diagnostics.sp_comp_diagnostics = diagnostics;
             }
         }
     }
 }
 
+/// Read a pipe of a child process to a [String] on a separate thread, so that the process does
+/// not block on a full pipe while we wait for it.
+///
+/// # Arguments
+///
+/// * `pipe` - Pipe to read from.
+fn spawn_pipe_reader<R: Read + Send + 'static>(pipe: Option<R>) -> JoinHandle<String> {
+    thread::spawn(move || {
+        let mut buf = vec![];
+        if let Some(mut pipe) = pipe {
+            let _ = pipe.read_to_end(&mut buf);
+        }
+        String::from_utf8_lossy(&buf).to_string()
+    })
+}
+
 /// Return a [vector](Vec) of [SPCompDiagnostics](SPCompDiagnostic) of the errors that spcomp threw.
 ///
 /// # Arguments
@@ -208,3 +357,77 @@ fn parse_spcomp_errors(stdout: &str) -> Vec<SPCompDiagnostic> {
         .flat_map(SPCompDiagnostic::from_spcomp_captures)
         .collect()
 }
+
+#[cfg(test)]
+mod tests {
+    use std::{
+        sync::Arc,
+        time::{Duration, Instant},
+    };
+
+    use lsp_types::{NumberOrString, Url};
+    use tempfile::tempdir;
+
+    use super::parse_spcomp_errors;
+    use crate::store::Store;
+
+    #[test]
+    fn parse_error_code_and_symbol() {
+        let stdout = "/home/user/scripting/foo.sp(12) : error 017: undefined symbol \"bar\"\n";
+        let diagnostics = parse_spcomp_errors(stdout);
+        assert_eq!(diagnostics.len(), 1);
+        assert_eq!(diagnostics[0].range.start.line, 11);
+        assert_eq!(diagnostics[0].symbol_name(), Some("bar"));
+        assert_eq!(
+            diagnostics[0].to_lsp_diagnostic().code,
+            Some(NumberOrString::Number(17))
+        );
+    }
+
+    #[test]
+    fn start_spcomp_run_cancels_the_previous_runs() {
+        let store = Store::new(false);
+        let uri = Url::parse("file:///scripting/foo.sp").unwrap();
+        let other_uri = Url::parse("file:///scripting/bar.sp").unwrap();
+        let first_run = store.start_spcomp_run(&uri);
+        let other_run = store.start_spcomp_run(&other_uri);
+        assert!(!first_run());
+
+        let second_run = store.start_spcomp_run(&uri);
+        assert!(first_run());
+        assert!(!second_run());
+        assert!(!other_run());
+
+        store.cancel_spcomp_runs(&uri);
+        assert!(second_run());
+        assert!(store.spcomp_runs.lock().unwrap().get(&uri).is_none());
+    }
+
+    #[cfg(unix)]
+    #[test]
+    fn cancelled_spcomp_run_is_killed() {
+        use std::os::unix::fs::PermissionsExt;
+
+        let directory = tempdir().unwrap();
+        let spcomp_path = directory.path().join("spcomp");
+        std::fs::write(&spcomp_path, "#!/bin/sh\nsleep 30\n").unwrap();
+        std::fs::set_permissions(&spcomp_path, std::fs::Permissions::from_mode(0o755)).unwrap();
+        let file_path = directory.path().join("foo.sp");
+        std::fs::write(&file_path, "").unwrap();
+
+        let mut store = Store::new(false);
+        let mut options = store.environment.options.as_ref().clone();
+        options.spcomp_path = spcomp_path;
+        store.environment.options = Arc::new(options);
+        let uri = Url::from_file_path(&file_path).unwrap();
+        let first_run = store.start_spcomp_run(&uri);
+        let _second_run = store.start_spcomp_run(&uri);
+
+        let now = Instant::now();
+        assert!(matches!(
+            store.get_spcomp_diagnostics(uri, first_run),
+            Ok(None)
+        ));
+        assert!(now.elapsed() < Duration::from_secs(10));
+    }
+}
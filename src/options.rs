@@ -1,4 +1,4 @@
-use std::path::PathBuf;
+use std::{collections::BTreeMap, path::PathBuf};
 
 use lsp_types::Url;
 use serde::{Deserialize, Serialize};
@@ -11,9 +11,141 @@ use crate::utils::normalize_uri;
 pub struct Options {
     pub includes_directories: Vec<PathBuf>,
     pub main_path: PathBuf,
+
+    /// Additional main paths, for workspaces which contain several plugins.
+    pub main_paths: Vec<PathBuf>,
     pub spcomp_path: PathBuf,
     pub linter_arguments: Vec<String>,
+    pub spcomp_mode: SpcompMode,
     pub disable_syntax_linter: bool,
+    pub formatter: FormatterOptions,
+    pub inlay_hints: InlayHintsOptions,
+
+    /// Macros defined before preprocessing every document, as if passed to spcomp with
+    /// `name=value`. An empty value defines the macro without a body.
+    pub predefined_macros: BTreeMap<String, String>,
+
+    /// Whether to disable the on-disk cache of the parsed files of the include directories.
+    pub disable_index_cache: bool,
+
+    /// Diagnostics of the code which is never used.
+    pub unused_diagnostics: UnusedDiagnosticsOptions,
+
+    /// Whether to disable the checks of the argument counts and tags, of the assignments to
+    /// const parameters and of the calls to unknown methods.
+    pub disable_checker_diagnostics: bool,
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub enum SpcompMode {
+    /// Compile the main path when the workspace is scanned or a file changes.
+    #[default]
+    MainPath,
+
+    /// Compile each `.sp` file on its own when it is opened or saved.
+    PerFile,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+#[serde(default)]
+pub struct FormatterOptions {
+    /// Indentation style. Falls back to the options sent by the client when [None].
+    pub indent_style: Option<IndentStyle>,
+
+    /// Number of spaces of an indentation level when using [IndentStyle::Space].
+    pub indent_width: u32,
+
+    /// Placement of the opening brace of blocks.
+    pub brace_style: BraceStyle,
+
+    /// Whether to put a space between a control keyword and its parenthesis, i.e `if (`.
+    pub space_after_keyword: bool,
+
+    /// Whether to surround binary and assignment operators with spaces.
+    pub space_around_operators: bool,
+
+    /// Whether to put a space after commas.
+    pub space_after_comma: bool,
+}
+
+impl Default for FormatterOptions {
+    fn default() -> Self {
+        Self {
+            indent_style: None,
+            indent_width: 4,
+            brace_style: BraceStyle::default(),
+            space_after_keyword: true,
+            space_around_operators: true,
+            space_after_comma: true,
+        }
+    }
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub enum IndentStyle {
+    Tab,
+    Space,
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub enum BraceStyle {
+    /// Keep the opening braces where they are.
+    #[default]
+    Preserve,
+
+    /// Put the opening brace on the same line as the statement, i.e `if (foo) {`.
+    SameLine,
+
+    /// Put the opening brace on its own line.
+    NextLine,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+#[serde(default)]
+pub struct InlayHintsOptions {
+    /// Whether to show the name of the parameters before the arguments of a call.
+    pub parameter_names: bool,
+
+    /// Whether to show the inferred type of the variables declared without a tag.
+    pub variable_types: bool,
+}
+
+impl Default for InlayHintsOptions {
+    fn default() -> Self {
+        Self {
+            parameter_names: true,
+            variable_types: true,
+        }
+    }
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+#[serde(default)]
+pub struct UnusedDiagnosticsOptions {
+    /// Whether to report the local variables and parameters which are never referenced.
+    pub variables: bool,
+
+    /// Whether to report the non public stock and static functions which are never called.
+    pub functions: bool,
+
+    /// Whether to report the includes whose items are never used by the including file.
+    pub includes: bool,
+}
+
+impl Default for UnusedDiagnosticsOptions {
+    fn default() -> Self {
+        Self {
+            variables: true,
+            functions: true,
+            includes: true,
+        }
+    }
 }
 
 impl Options {
@@ -60,6 +192,28 @@ impl Options {
         ))
     }
 
+    /// Return the [uris](lsp_types::Url) of the main path and of the additional main paths
+    /// which exist.
+    pub fn get_main_paths_uris(&self) -> Vec<Url> {
+        let mut uris = vec![];
+        if let Ok(Some(main_path_uri)) = self.get_main_path_uri() {
+            uris.push(main_path_uri);
+        }
+        for path in self.main_paths.iter() {
+            if !path.is_file() {
+                continue;
+            }
+            if let Ok(mut uri) = Url::from_file_path(path) {
+                normalize_uri(&mut uri);
+                if !uris.contains(&uri) {
+                    uris.push(uri);
+                }
+            }
+        }
+
+        uris
+    }
+
     /// Returns true if the given path is a parent or one of the IncludeDirectories.
     ///
     /// # Arguments
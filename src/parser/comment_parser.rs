@@ -15,7 +15,21 @@ use crate::{
 
 impl Walker {
     pub fn push_comment(&mut self, node: Node, source: &str) {
-        self.comments.push(Comment::new(node, source));
+        let comment = Comment::new(node, source);
+        self.push_comment_block(comment.range);
+        self.comments.push(comment);
+    }
+
+    /// Extend the last block of comments with a comment if it starts on the next line, or start
+    /// a new block.
+    fn push_comment_block(&mut self, range: Range) {
+        if let Some(last_block) = self.comment_blocks.last_mut() {
+            if last_block.end.line + 1 == range.start.line {
+                last_block.end = range.end;
+                return;
+            }
+        }
+        self.comment_blocks.push(range);
     }
 
     pub fn push_deprecated(&mut self, node: Node, source: &str) -> anyhow::Result<()> {
@@ -182,6 +182,7 @@ impl Document {
             references: vec![],
             parent: parent.as_ref().map(Arc::downgrade),
             params: vec![],
+            is_variadic: false,
             children: vec![],
         };
 
@@ -219,6 +220,12 @@ impl Document {
             argument_declarations_node.context("No argument declarations node")?;
         let mut cursor = argument_declarations_node.walk();
         for child in argument_declarations_node.children(&mut cursor) {
+            if child.kind() == "rest_argument" {
+                if let SPItem::Function(function_item) = &mut *function_item.write().unwrap() {
+                    function_item.is_variadic = true;
+                }
+                continue;
+            }
             let _ = self.read_function_parameter(&child, &description, &function_item);
         }
 
@@ -239,6 +246,7 @@ impl Document {
             .context("Function parameter does not have a name.")?;
         let type_node = child.child_by_field_name("type");
         let mut is_const = false;
+        let mut has_default = false;
         let mut dimensions = vec![];
         let mut storage_class: Vec<VariableStorageClass> = vec![];
         let mut sub_cursor = child.walk();
@@ -248,6 +256,7 @@ impl Document {
                     is_const = true;
                     storage_class.push(VariableStorageClass::Const);
                 }
+                "=" => has_default = true,
                 "dimension" | "fixed_dimension" => {
                     let dimension = sub_child.utf8_text(self.preprocessed_text.as_bytes())?;
                     dimensions.push(dimension.to_string());
@@ -293,6 +302,7 @@ impl Document {
             type_: self.parse_argument_type(type_node),
             description,
             dimensions,
+            has_default,
         };
         function_item
             .write()
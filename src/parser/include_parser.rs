@@ -13,7 +13,7 @@ use crate::{
 
 impl Store {
     pub(crate) fn parse_include(
-        &mut self,
+        &self,
         document: &mut Document,
         node: &mut Node,
     ) -> anyhow::Result<()> {
@@ -51,7 +51,7 @@ impl Store {
     /// * `documents` - Set of known documents.
     /// * `document_uri` - Uri of the document where the include declaration is parsed from.
     pub(crate) fn resolve_import(
-        &mut self,
+        &self,
         include_text: &mut String,
         document_uri: &Arc<Url>,
         quoted: bool,
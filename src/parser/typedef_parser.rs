@@ -127,11 +127,13 @@ impl Document {
             .context("Argument name is empty.")?;
         let argument_type_node = child.child_by_field_name("type");
         let mut is_const = false;
+        let mut has_default = false;
         let mut dimensions = vec![];
         let mut sub_cursor = child.walk();
         for sub_child in child.children(&mut sub_cursor) {
             match sub_child.kind() {
                 "const" => is_const = true,
+                "=" => has_default = true,
                 "dimension" | "fixed_dimension" => {
                     let dimension = sub_child.utf8_text(self.preprocessed_text.as_bytes())?;
                     dimensions.push(dimension.to_string());
@@ -149,6 +151,7 @@ impl Document {
                 deprecated: None,
             },
             dimensions,
+            has_default,
         };
         parent
             .write()
@@ -0,0 +1,374 @@
+use std::{collections::HashMap, path::Path};
+
+use lazy_static::lazy_static;
+use lsp_types::{
+    CodeAction, CodeActionKind, CodeActionOrCommand, CodeActionParams, Diagnostic, NumberOrString,
+    Position, Range, TextEdit, Url, WorkspaceEdit,
+};
+use regex::Regex;
+
+use crate::{document::Document, linter::UNDEFINED_SYMBOL_CODE, spitem::SPItem, store::Store};
+
+use super::FeatureRequest;
+
+pub fn provide_code_action(
+    request: FeatureRequest<CodeActionParams>,
+) -> Option<Vec<CodeActionOrCommand>> {
+    let document = request.store.documents.get(&request.uri)?;
+    let mut actions = vec![];
+    actions.extend(missing_include_actions(&request, document));
+    actions.extend(undefined_symbol_actions(&request));
+    actions.extend(deprecated_actions(&request));
+    actions.extend(unused_include_actions(&request));
+
+    Some(
+        actions
+            .into_iter()
+            .map(CodeActionOrCommand::CodeAction)
+            .collect(),
+    )
+}
+
+/// Build the code actions which add an `#include` for the unresolved symbols of the requested
+/// range, when the symbol is declared in a known include file.
+///
+/// # Arguments
+///
+/// * `request` - Code action request.
+/// * `document` - Document of the request.
+fn missing_include_actions(
+    request: &FeatureRequest<CodeActionParams>,
+    document: &Document,
+) -> Vec<CodeAction> {
+    let mut actions = vec![];
+    let mut unresolved_tokens: Vec<(&String, &Range)> = document
+        .unresolved_tokens
+        .iter()
+        .filter_map(|(text, ranges)| {
+            ranges
+                .iter()
+                .find(|range| ranges_overlap(range, &request.params.range))
+                .map(|range| (text, range))
+        })
+        .collect();
+    unresolved_tokens.sort_by_key(|(_, range)| range.start);
+    for (text, range) in unresolved_tokens {
+        for (include_uri, include_document) in request.store.documents.iter() {
+            // Only suggest the include files, a plugin can't include another plugin.
+            if include_uri == &request.uri
+                || !include_uri.path().ends_with(".inc")
+                || !declares(include_document, text)
+            {
+                continue;
+            }
+            let Some(include_text) = include_text(&request.store, include_uri, &request.uri) else {
+                continue;
+            };
+            let line = last_include_line(document).map_or(0, |line| line + 1);
+            actions.push(CodeAction {
+                title: format!("Add `#include {}`", include_text),
+                kind: Some(CodeActionKind::QUICKFIX),
+                diagnostics: related_diagnostics(request, range),
+                edit: Some(workspace_edit(
+                    &request.uri,
+                    vec![TextEdit::new(
+                        Range::new(Position::new(line, 0), Position::new(line, 0)),
+                        format!("#include {}\n", include_text),
+                    )],
+                )),
+                ..Default::default()
+            });
+        }
+    }
+
+    actions
+}
+
+/// Build the code actions which replace the undefined symbols of the diagnostics of the request
+/// with the names suggested by the linter.
+///
+/// # Arguments
+///
+/// * `request` - Code action request.
+fn undefined_symbol_actions(request: &FeatureRequest<CodeActionParams>) -> Vec<CodeAction> {
+    let mut actions = vec![];
+    let code = NumberOrString::String(UNDEFINED_SYMBOL_CODE.to_string());
+    for diagnostic in request.params.context.diagnostics.iter() {
+        if diagnostic.code.as_ref() != Some(&code) {
+            continue;
+        }
+        let Some(suggestions) = diagnostic
+            .data
+            .clone()
+            .and_then(|data| serde_json::from_value::<Vec<String>>(data).ok())
+        else {
+            continue;
+        };
+        for (i, suggestion) in suggestions.into_iter().enumerate() {
+            actions.push(CodeAction {
+                title: format!("Change to `{}`", suggestion),
+                kind: Some(CodeActionKind::QUICKFIX),
+                diagnostics: Some(vec![diagnostic.clone()]),
+                edit: Some(workspace_edit(
+                    &request.uri,
+                    vec![TextEdit::new(diagnostic.range, suggestion)],
+                )),
+                is_preferred: Some(i == 0),
+                ..Default::default()
+            });
+        }
+    }
+
+    actions
+}
+
+/// Build the code actions which replace the deprecated items of the requested range with the
+/// replacement suggested in their `#pragma deprecated` message.
+///
+/// # Arguments
+///
+/// * `request` - Code action request.
+fn deprecated_actions(request: &FeatureRequest<CodeActionParams>) -> Vec<CodeAction> {
+    let mut actions = vec![];
+    let (all_items_flat, _) = request.store.get_all_items(true);
+    for item in all_items_flat.iter() {
+        let item = item.read().unwrap();
+        let Some(deprecated) = item
+            .description()
+            .and_then(|description| description.deprecated)
+        else {
+            continue;
+        };
+        let Some(replacement) = deprecated_replacement(&deprecated) else {
+            continue;
+        };
+        if replacement == item.name() {
+            continue;
+        }
+        let Some(references) = item.references() else {
+            continue;
+        };
+        for reference in references.iter() {
+            if reference.uri != request.uri
+                || !ranges_overlap(&reference.v_range, &request.params.range)
+            {
+                continue;
+            }
+            actions.push(CodeAction {
+                title: format!("Replace `{}` with `{}`", item.name(), replacement),
+                kind: Some(CodeActionKind::QUICKFIX),
+                diagnostics: related_diagnostics(request, &reference.v_range),
+                edit: Some(workspace_edit(
+                    &request.uri,
+                    vec![TextEdit::new(reference.v_range, replacement.clone())],
+                )),
+                is_preferred: Some(true),
+                ..Default::default()
+            });
+        }
+    }
+
+    actions
+}
+
+/// Build the code actions which remove the unused includes of the document.
+///
+/// # Arguments
+///
+/// * `request` - Code action request.
+fn unused_include_actions(request: &FeatureRequest<CodeActionParams>) -> Vec<CodeAction> {
+    let mut actions = vec![];
+    if !request
+        .store
+        .environment
+        .options
+        .unused_diagnostics
+        .includes
+    {
+        return actions;
+    }
+    let unused_includes = request.store.get_unused_includes(&request.uri);
+    let edits: Vec<TextEdit> = unused_includes
+        .iter()
+        .map(|include| {
+            let line = include.v_range.start.line;
+            TextEdit::new(
+                Range::new(Position::new(line, 0), Position::new(line + 1, 0)),
+                String::new(),
+            )
+        })
+        .collect();
+    for (include, edit) in unused_includes.iter().zip(edits.iter()) {
+        if include.v_range.start.line < request.params.range.start.line
+            || include.v_range.start.line > request.params.range.end.line
+        {
+            continue;
+        }
+        actions.push(CodeAction {
+            title: format!("Remove unused include `{}`", include.name),
+            kind: Some(CodeActionKind::QUICKFIX),
+            diagnostics: related_diagnostics(request, &include.v_range),
+            edit: Some(workspace_edit(&request.uri, vec![edit.clone()])),
+            ..Default::default()
+        });
+    }
+    if !edits.is_empty() {
+        actions.push(CodeAction {
+            title: "Remove all unused includes".to_string(),
+            kind: Some(CodeActionKind::SOURCE),
+            edit: Some(workspace_edit(&request.uri, edits)),
+            ..Default::default()
+        });
+    }
+
+    actions
+}
+
+/// Extract the name of the replacement of a deprecated item from its deprecation message,
+/// i.e `Use GetClientAuthId instead.`.
+///
+/// # Arguments
+///
+/// * `deprecated` - Deprecation message.
+fn deprecated_replacement(deprecated: &str) -> Option<String> {
+    lazy_static! {
+        static ref RE: Regex = Regex::new(r"\b[Uu]se\s+([A-Z]\w*)\b([.:(]?)").unwrap();
+    }
+    let captures = RE.captures(deprecated)?;
+    if matches!(captures.get(2)?.as_str(), "." | ":") {
+        // Methods and static methods cannot replace a call to a function.
+        return None;
+    }
+
+    Some(captures.get(1)?.as_str().to_string())
+}
+
+/// Whether a document declares a top level item, or an enum member, with the given name.
+fn declares(document: &Document, name: &str) -> bool {
+    document.sp_items.iter().any(|item| {
+        let item = item.read().unwrap();
+        match &*item {
+            SPItem::Include(_) => false,
+            SPItem::Enum(enum_item) => {
+                enum_item.name == name
+                    || enum_item
+                        .children
+                        .iter()
+                        .any(|child| child.read().unwrap().name() == name)
+            }
+            _ => item.name() == name,
+        }
+    })
+}
+
+/// Build the text of the `#include` directive needed to include a file from a document, such
+/// as `<sdktools>` or `"utils.sp"`. Return [None] if the file cannot be reached from the include
+/// directories or from the directory of the document.
+///
+/// # Arguments
+///
+/// * `store` - [Store] of the server.
+/// * `include_uri` - [Uri](Url) of the file to include.
+/// * `uri` - [Uri](Url) of the document which includes the file.
+fn include_text(store: &Store, include_uri: &Url, uri: &Url) -> Option<String> {
+    let include_path = include_uri.to_file_path().ok()?;
+    let document_dir = uri.to_file_path().ok()?.parent()?.to_path_buf();
+    let mut directories = store.environment.options.get_all_possible_include_folders();
+    directories.push(document_dir.join("include"));
+    directories.push(document_dir);
+    for directory in directories.iter() {
+        let Ok(relative_path) = include_path.strip_prefix(directory) else {
+            continue;
+        };
+        let relative_path = path_to_include(relative_path)?;
+        return Some(match relative_path.strip_suffix(".inc") {
+            Some(relative_path) => format!("<{}>", relative_path),
+            None => format!("\"{}\"", relative_path),
+        });
+    }
+
+    None
+}
+
+fn path_to_include(path: &Path) -> Option<String> {
+    let components: Option<Vec<&str>> = path
+        .components()
+        .map(|component| component.as_os_str().to_str())
+        .collect();
+
+    Some(components?.join("/"))
+}
+
+/// Return the line of the last `#include` or `#tryinclude` directive of a document, if any.
+///
+/// The original text is scanned, so that the includes which could not be resolved are found as
+/// well.
+fn last_include_line(document: &Document) -> Option<u32> {
+    document
+        .text
+        .lines()
+        .enumerate()
+        .filter(|(_, line)| {
+            line.trim_start()
+                .strip_prefix('#')
+                .map_or(false, |directive| {
+                    let directive = directive.trim_start();
+                    directive.starts_with("include") || directive.starts_with("tryinclude")
+                })
+        })
+        .map(|(line, _)| line as u32)
+        .last()
+}
+
+/// Return the diagnostics of the request which overlap with a range.
+fn related_diagnostics(
+    request: &FeatureRequest<CodeActionParams>,
+    range: &Range,
+) -> Option<Vec<Diagnostic>> {
+    let diagnostics: Vec<Diagnostic> = request
+        .params
+        .context
+        .diagnostics
+        .iter()
+        .filter(|diagnostic| ranges_overlap(&diagnostic.range, range))
+        .cloned()
+        .collect();
+    if diagnostics.is_empty() {
+        return None;
+    }
+
+    Some(diagnostics)
+}
+
+fn workspace_edit(uri: &Url, edits: Vec<TextEdit>) -> WorkspaceEdit {
+    let mut changes = HashMap::new();
+    changes.insert(uri.clone(), edits);
+
+    WorkspaceEdit {
+        changes: Some(changes),
+        ..Default::default()
+    }
+}
+
+fn ranges_overlap(a: &Range, b: &Range) -> bool {
+    a.start <= b.end && b.start <= a.end
+}
+
+#[cfg(test)]
+mod tests {
+    use super::deprecated_replacement;
+
+    #[test]
+    fn deprecated_replacement_extracts_function_names() {
+        assert_eq!(
+            deprecated_replacement("Use GetClientAuthId"),
+            Some("GetClientAuthId".to_string())
+        );
+        assert_eq!(
+            deprecated_replacement("No longer supported, use SetEntPropEnt() instead."),
+            Some("SetEntPropEnt".to_string())
+        );
+        assert_eq!(deprecated_replacement("Use Handle.Close"), None);
+        assert_eq!(deprecated_replacement("Do not use this."), None);
+    }
+}
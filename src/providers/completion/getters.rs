@@ -1,8 +1,13 @@
 use std::sync::{Arc, RwLock};
 
-use lsp_types::{CompletionList, CompletionParams, Position, Range};
+use lsp_types::{CompletionItem, CompletionList, CompletionParams, Position, Range};
+use tree_sitter::Point;
 
-use crate::{providers::FeatureRequest, spitem::SPItem};
+use crate::{
+    providers::FeatureRequest,
+    semantic_analyzer::type_resolver::{expression_ending_at, ExpressionType, TypeResolver},
+    spitem::SPItem,
+};
 
 use super::{context::get_line_words, defaults::get_default_completions};
 
@@ -109,6 +114,11 @@ pub(super) fn get_method_completions(
     position: Position,
     request: FeatureRequest<CompletionParams>,
 ) -> Option<CompletionList> {
+    if let Some(completions) =
+        get_resolved_method_completions(&all_items, pre_line, position, &request)
+    {
+        return Some(completions);
+    }
     let words = get_line_words(pre_line, position);
     for word in words.into_iter().flatten().rev() {
         let word_pos = Position {
@@ -141,30 +151,9 @@ pub(super) fn get_method_completions(
                     let mut children = mm_item.children;
                     extend_children(&mut children, &mm_item.parent);
                     let mut items = vec![];
+                    let is_static = is_static_call(item, type_item);
                     for child in children.iter() {
-                        match &*child.read().unwrap() {
-                            SPItem::Function(method_item) => {
-                                if method_item.is_ctor() {
-                                    // We don't want constructors here.
-                                    continue;
-                                }
-                                if is_static_call(item, type_item) {
-                                    // We are trying to call static methods.
-                                    if method_item.is_static() {
-                                        items.extend(
-                                            method_item.to_completions(&request.params, true),
-                                        );
-                                    }
-                                } else if !method_item.is_static() {
-                                    // We are trying to call non static methods.
-                                    items.extend(method_item.to_completions(&request.params, true));
-                                }
-                            }
-                            SPItem::Property(property_item) => {
-                                items.extend(property_item.to_completion(&request.params, true))
-                            }
-                            _ => {}
-                        }
+                        push_member_completions(&mut items, child, is_static, &request.params);
                     }
                     return Some(CompletionList {
                         items,
@@ -189,6 +178,93 @@ pub(super) fn get_method_completions(
     None
 }
 
+/// Return a [CompletionList](lsp_types::CompletionList) of the members of the expression before
+/// the `.` or `::` of the line, using the type of the expression resolved from the tree of the
+/// document. This handles chains such as `GetMenu().ItemCount` or `view_as<ArrayList>(x).Length`.
+///
+/// # Arguments
+///
+/// * `all_items` - Vector of [SPItem](crate::spitem::SPItem).
+/// * `pre_line` - Prefix line of the document to analyze.
+/// * `position` - [Position](lsp_types::Position) of the request.
+/// * `request` - Completion request.
+fn get_resolved_method_completions(
+    all_items: &[Arc<RwLock<SPItem>>],
+    pre_line: &str,
+    position: Position,
+    request: &FeatureRequest<CompletionParams>,
+) -> Option<CompletionList> {
+    let document = request.store.documents.get(&request.uri)?;
+    let tree = document.tree.as_ref()?;
+    let end = Point::new(position.line as usize, receiver_end(pre_line)?);
+    let node = expression_ending_at(tree.root_node(), end)?;
+    let resolver = TypeResolver::new(all_items, document);
+    let type_ = resolver.expression_type(node)?;
+    resolver.type_item(type_.name())?;
+    let is_static = matches!(type_, ExpressionType::Type(_));
+    let mut items = vec![];
+    for member in resolver.members(&type_).iter() {
+        push_member_completions(&mut items, member, is_static, &request.params);
+    }
+
+    Some(CompletionList {
+        items,
+        ..Default::default()
+    })
+}
+
+/// Push the completions of a member of a methodmap or of an enum struct.
+///
+/// # Arguments
+///
+/// * `items` - Vector of completions to push into.
+/// * `member` - [SPItem](crate::spitem::SPItem) of the member.
+/// * `is_static` - Whether the members are accessed from the type rather than from a value.
+/// * `params` - [Parameters](lsp_types::completion::CompletionParams) of the completion request.
+fn push_member_completions(
+    items: &mut Vec<CompletionItem>,
+    member: &Arc<RwLock<SPItem>>,
+    is_static: bool,
+    params: &CompletionParams,
+) {
+    match &*member.read().unwrap() {
+        SPItem::Function(method_item) => {
+            // We don't want constructors here, and static methods are only called from the type.
+            if !method_item.is_ctor() && method_item.is_static() == is_static {
+                items.extend(method_item.to_completions(params, true));
+            }
+        }
+        SPItem::Property(property_item) => items.extend(property_item.to_completion(params, true)),
+        member => items.extend(member.to_completions(params, true)),
+    }
+}
+
+/// Return the column right after the expression whose members are completed, i.e before the `.`
+/// or `::` which precedes the word being typed.
+///
+/// # Arguments
+///
+/// * `pre_line` - Prefix line of the document to analyze.
+fn receiver_end(pre_line: &str) -> Option<usize> {
+    let chars: Vec<char> = pre_line.chars().collect();
+    let mut end = chars.len();
+    while end > 0 && (chars[end - 1].is_alphanumeric() || chars[end - 1] == '_') {
+        end -= 1;
+    }
+    if end > 0 && chars[end - 1] == '.' {
+        end -= 1;
+    } else if end > 1 && chars[end - 1] == ':' && chars[end - 2] == ':' {
+        end -= 2;
+    } else {
+        return None;
+    }
+    while end > 0 && chars[end - 1].is_whitespace() {
+        end -= 1;
+    }
+
+    Some(end)
+}
+
 fn extend_children(children: &mut Vec<Arc<RwLock<SPItem>>>, mm_item: &Option<Arc<RwLock<SPItem>>>) {
     if let Some(mm_item) = mm_item {
         if let SPItem::Methodmap(mm_item) = &*mm_item.read().unwrap() {
@@ -216,3 +292,16 @@ fn extend_children(children: &mut Vec<Arc<RwLock<SPItem>>>, mm_item: &Option<Arc
 fn is_static_call(item: &Arc<RwLock<SPItem>>, type_item: &Arc<RwLock<SPItem>>) -> bool {
     item.read().unwrap().name() == type_item.read().unwrap().name()
 }
+
+#[cfg(test)]
+mod tests {
+    use super::receiver_end;
+
+    #[test]
+    fn receiver_end_skips_the_typed_member() {
+        assert_eq!(receiver_end("    GetMenu()."), Some(13));
+        assert_eq!(receiver_end("    list[i].Len"), Some(11));
+        assert_eq!(receiver_end("Foo::"), Some(3));
+        assert_eq!(receiver_end("int foo"), None);
+    }
+}
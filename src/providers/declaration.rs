@@ -0,0 +1,142 @@
+use lsp_types::request::{GotoDeclarationParams, GotoDeclarationResponse};
+
+use crate::{
+    spitem::{function_item::FunctionDefinitionType, SPItem},
+    utils::range_contains_pos,
+};
+
+use super::FeatureRequest;
+
+/// Build the declarations of the function under the cursor, which are the `forward` and `native`
+/// prototypes with the same name in `.inc` files.
+///
+/// # Arguments
+///
+/// * `request` - Declaration request object [FeatureRequest<GotoDeclarationParams>].
+pub fn provide_declaration(
+    request: FeatureRequest<GotoDeclarationParams>,
+) -> Option<GotoDeclarationResponse> {
+    let position = request.params.text_document_position_params.position;
+    let uri = &request
+        .params
+        .text_document_position_params
+        .text_document
+        .uri;
+    let items = &request.store.get_items_from_position(position, uri.clone());
+    let names: Vec<String> = items
+        .iter()
+        .filter_map(|item| match &*item.read().unwrap() {
+            SPItem::Function(function_item) => Some(function_item.name.clone()),
+            _ => None,
+        })
+        .collect();
+    if names.is_empty() {
+        return None;
+    }
+    let mut declarations = vec![];
+    for item in request.store.get_all_items(false).0.iter() {
+        let item = item.read().unwrap();
+        if let SPItem::Function(function_item) = &*item {
+            // The cursor may already be on a prototype, which is not its own declaration.
+            if *function_item.uri == *uri && range_contains_pos(function_item.v_range, position) {
+                continue;
+            }
+            if matches!(
+                function_item.definition_type,
+                FunctionDefinitionType::Forward | FunctionDefinitionType::Native
+            ) && function_item.uri.path().ends_with(".inc")
+                && names.contains(&function_item.name)
+            {
+                declarations.extend(item.to_definition(&request.params));
+            }
+        }
+    }
+
+    Some(GotoDeclarationResponse::Link(declarations))
+}
+
+#[cfg(test)]
+mod tests {
+    use std::sync::Arc;
+
+    use lsp_types::{request::GotoDeclarationResponse, GotoDefinitionParams, Location};
+
+    use super::provide_declaration;
+    use crate::{providers::FeatureRequest, tests::fixtures::TestStore};
+
+    fn declarations(test_store: &TestStore) -> Vec<Location> {
+        let cursor = test_store.cursor().unwrap();
+        let uri = Arc::new(cursor.text_document.uri.clone());
+        let request = FeatureRequest {
+            params: GotoDefinitionParams {
+                text_document_position_params: cursor,
+                work_done_progress_params: Default::default(),
+                partial_result_params: Default::default(),
+            },
+            store: test_store.store.clone(),
+            uri,
+        };
+
+        match provide_declaration(request) {
+            Some(GotoDeclarationResponse::Link(links)) => links
+                .into_iter()
+                .map(|link| Location::new(link.target_uri, link.target_selection_range))
+                .collect(),
+            _ => vec![],
+        }
+    }
+
+    #[test]
+    fn forward_of_a_public_function() {
+        let test_store = TestStore::new(
+            r#"
+//! foo.inc
+forward void OnFoo(int a);
+             ^^^^^
+
+//! main.sp
+public void OnFoo(int a)
+             |
+{
+}
+"#,
+        )
+        .unwrap();
+        assert_eq!(declarations(&test_store), test_store.locations());
+    }
+
+    #[test]
+    fn native_of_a_call() {
+        let test_store = TestStore::new(
+            r#"
+//! foo.inc
+native int Bar();
+           ^^^
+
+//! main.sp
+native int Bar();
+
+void foo()
+{
+    Bar();
+    |
+}
+"#,
+        )
+        .unwrap();
+        assert_eq!(declarations(&test_store), test_store.locations());
+    }
+
+    #[test]
+    fn prototype_is_not_its_own_declaration() {
+        let test_store = TestStore::new(
+            r#"
+//! foo.inc
+native int Bar();
+            |
+"#,
+        )
+        .unwrap();
+        assert!(declarations(&test_store).is_empty());
+    }
+}
@@ -0,0 +1,245 @@
+use lsp_types::{DocumentHighlight, DocumentHighlightKind, DocumentHighlightParams, Range};
+use tree_sitter::{Node, Point};
+
+use super::FeatureRequest;
+
+/// Build the highlights of the occurrences in the document of the item under the cursor. The
+/// occurrences which are assigned to are marked as writes, as well as the declaration of the item
+/// when it has an initial value.
+///
+/// # Arguments
+///
+/// * `request` - Document highlight request object [FeatureRequest<DocumentHighlightParams>].
+pub fn provide_document_highlight(
+    request: FeatureRequest<DocumentHighlightParams>,
+) -> Option<Vec<DocumentHighlight>> {
+    let uri = &request
+        .params
+        .text_document_position_params
+        .text_document
+        .uri;
+    let items = request.store.get_items_from_position(
+        request.params.text_document_position_params.position,
+        uri.clone(),
+    );
+    let document = request.store.documents.get(uri)?;
+    let root_node = document.tree.as_ref().map(|tree| tree.root_node());
+    let mut highlights = vec![];
+    for item in items.iter() {
+        let item = item.read().unwrap();
+        if *item.uri() == *uri {
+            let has_value = root_node.map_or(false, |root_node| {
+                has_initial_value(root_node, &item.range())
+            });
+            highlights.push(DocumentHighlight {
+                range: item.v_range(),
+                kind: Some(if has_value {
+                    DocumentHighlightKind::WRITE
+                } else {
+                    DocumentHighlightKind::TEXT
+                }),
+            });
+        }
+        let references = match item.references() {
+            Some(references) => references,
+            None => continue,
+        };
+        for reference in references.iter().filter(|reference| *reference.uri == *uri) {
+            let is_write = root_node.map_or(false, |root_node| {
+                is_write_access(root_node, &reference.range)
+            });
+            highlights.push(DocumentHighlight {
+                range: reference.v_range,
+                kind: Some(if is_write {
+                    DocumentHighlightKind::WRITE
+                } else {
+                    DocumentHighlightKind::READ
+                }),
+            });
+        }
+    }
+    highlights.sort_by_key(|highlight| highlight.range.start);
+    highlights.dedup_by_key(|highlight| highlight.range);
+
+    Some(highlights)
+}
+
+/// Return whether the symbol at a range is assigned to, i.e it is the target of an assignment
+/// or of an increment or decrement, possibly through an array index or a field access.
+///
+/// # Arguments
+///
+/// * `root_node` - Root [Node] of the tree of the document.
+/// * `range` - [Range] of the symbol in the preprocessed text.
+fn is_write_access(root_node: Node, range: &Range) -> bool {
+    let mut node = match node_at(root_node, range) {
+        Some(node) => node,
+        None => return false,
+    };
+    while let Some(parent) = node.parent() {
+        match parent.kind() {
+            "assignment_expression" => return parent.child(0) == Some(node),
+            "update_expression" => return true,
+            // `foo[0] = 1` assigns to `foo`.
+            "array_indexed_access" if parent.named_child(0) == Some(node) => node = parent,
+            // `foo.bar = 1` assigns to `bar`.
+            "field_access"
+                if parent
+                    .named_child_count()
+                    .checked_sub(1)
+                    .and_then(|index| parent.named_child(index))
+                    == Some(node) =>
+            {
+                node = parent
+            }
+            _ => return false,
+        }
+    }
+
+    false
+}
+
+/// Return whether the declaration of the symbol at a range assigns a value to it, e.g
+/// `int foo = 1;` or a parameter with a default value.
+///
+/// # Arguments
+///
+/// * `root_node` - Root [Node] of the tree of the document.
+/// * `range` - [Range] of the name of the declaration in the preprocessed text.
+fn has_initial_value(root_node: Node, range: &Range) -> bool {
+    let Some(declaration_node) = node_at(root_node, range).and_then(|node| node.parent()) else {
+        return false;
+    };
+    if !matches!(
+        declaration_node.kind(),
+        "variable_declaration" | "old_variable_declaration" | "argument_declaration" | "enum_entry"
+    ) {
+        return false;
+    }
+    let mut cursor = declaration_node.walk();
+    let has_value = declaration_node
+        .children(&mut cursor)
+        .any(|child| child.kind() == "=");
+
+    has_value
+}
+
+/// Return the smallest [Node] which spans a range of the preprocessed text.
+fn node_at<'a>(root_node: Node<'a>, range: &Range) -> Option<Node<'a>> {
+    root_node.descendant_for_point_range(
+        Point::new(range.start.line as usize, range.start.character as usize),
+        Point::new(range.end.line as usize, range.end.character as usize),
+    )
+}
+
+#[cfg(test)]
+mod tests {
+    use std::sync::Arc;
+
+    use lsp_types::{DocumentHighlightKind, DocumentHighlightParams, Position, Range};
+
+    use super::provide_document_highlight;
+    use crate::{providers::FeatureRequest, tests::fixtures::TestStore};
+
+    fn document_highlights(fixture: &str) -> Vec<(Range, DocumentHighlightKind)> {
+        let test_store = TestStore::new(fixture).unwrap();
+        let cursor = test_store.cursor().unwrap();
+        let uri = Arc::new(cursor.text_document.uri.clone());
+        let request = FeatureRequest {
+            params: DocumentHighlightParams {
+                text_document_position_params: cursor,
+                work_done_progress_params: Default::default(),
+                partial_result_params: Default::default(),
+            },
+            store: test_store.store.clone(),
+            uri,
+        };
+
+        provide_document_highlight(request)
+            .unwrap()
+            .into_iter()
+            .map(|highlight| (highlight.range, highlight.kind.unwrap()))
+            .collect()
+    }
+
+    fn range(line: u32, start: u32, end: u32) -> Range {
+        Range::new(Position::new(line, start), Position::new(line, end))
+    }
+
+    #[test]
+    fn reads_and_writes_of_a_variable() {
+        assert_eq!(
+            document_highlights(
+                r#"
+//! main.sp
+void foo()
+{
+    int bar = 1;
+        |
+    bar = bar + 1;
+    bar++;
+    int baz[2];
+    baz[0] = bar;
+}
+"#
+            ),
+            vec![
+                (range(2, 8, 11), DocumentHighlightKind::WRITE),
+                (range(3, 4, 7), DocumentHighlightKind::WRITE),
+                (range(3, 10, 13), DocumentHighlightKind::READ),
+                (range(4, 4, 7), DocumentHighlightKind::WRITE),
+                (range(6, 13, 16), DocumentHighlightKind::READ),
+            ]
+        );
+    }
+
+    #[test]
+    fn declaration_without_value_is_text() {
+        assert_eq!(
+            document_highlights(
+                r#"
+//! main.sp
+int foo[2];
+    |
+
+void bar()
+{
+    foo[0] = foo[1];
+}
+"#
+            ),
+            vec![
+                (range(0, 4, 7), DocumentHighlightKind::TEXT),
+                (range(4, 4, 7), DocumentHighlightKind::WRITE),
+                (range(4, 13, 16), DocumentHighlightKind::READ),
+            ]
+        );
+    }
+
+    #[test]
+    fn occurrences_in_other_documents_are_ignored() {
+        assert_eq!(
+            document_highlights(
+                r#"
+//! foo.inc
+int foo;
+
+void bar()
+{
+    foo = 1;
+}
+
+//! main.sp
+#include "foo.inc"
+
+void baz()
+{
+    foo++;
+    |
+}
+"#
+            ),
+            vec![(range(4, 4, 7), DocumentHighlightKind::WRITE)]
+        );
+    }
+}
@@ -0,0 +1,423 @@
+use std::{
+    collections::HashMap,
+    path::{Path, PathBuf},
+};
+
+use lsp_types::{RenameFilesParams, TextEdit, Url, WorkspaceEdit};
+
+use crate::{document::Document, spitem::SPItem, store::Store};
+
+use super::WorkspaceRequest;
+
+/// Build the edits of the include paths which point to the renamed files or folders, or which
+/// are declared in them. The `<>` or `""` form of the includes is kept.
+///
+/// # Arguments
+///
+/// * `request` - Will rename files request object [WorkspaceRequest<RenameFilesParams>].
+pub fn provide_will_rename_files(
+    request: WorkspaceRequest<RenameFilesParams>,
+) -> Option<WorkspaceEdit> {
+    let renames = renamed_paths(&request.params);
+    let mut changes: HashMap<Url, Vec<TextEdit>> = HashMap::new();
+    for document in request.store.documents.values() {
+        let Ok(document_path) = document.uri.to_file_path() else {
+            continue;
+        };
+        let new_document_path = renamed_path(&document_path, &renames);
+        if new_document_path.is_none()
+            && !document.includes.keys().any(|include_uri| {
+                include_uri
+                    .to_file_path()
+                    .map_or(false, |path| renamed_path(&path, &renames).is_some())
+            })
+        {
+            continue;
+        }
+        let new_document_path = new_document_path.unwrap_or_else(|| document_path.clone());
+        // The main path of the document may be renamed as well.
+        let main_path = request
+            .store
+            .main_uri_of(&document.uri)
+            .and_then(|main_uri| main_uri.to_file_path().ok());
+        let new_main_path = main_path.as_ref().map(|main_path| {
+            renamed_path(main_path, &renames).unwrap_or_else(|| main_path.clone())
+        });
+        let edits: Vec<TextEdit> = document
+            .sp_items
+            .iter()
+            .filter_map(|item| match &*item.read().unwrap() {
+                SPItem::Include(include_item) => {
+                    let old_target = include_item.include_uri.to_file_path().ok()?;
+                    let new_target =
+                        renamed_path(&old_target, &renames).unwrap_or_else(|| old_target.clone());
+                    let text = include_text(document, include_item.v_range)?;
+                    let quoted = text.starts_with('"');
+                    let path = text.get(1..text.len() - 1)?.trim();
+                    let directories = candidate_directories(
+                        include_directories(
+                            &request.store,
+                            &document_path,
+                            main_path.as_deref(),
+                            quoted,
+                        ),
+                        include_directories(
+                            &request.store,
+                            &new_document_path,
+                            new_main_path.as_deref(),
+                            quoted,
+                        ),
+                        &include_item.name,
+                        &old_target,
+                    );
+                    let Some(new_path) = new_include_path(path, &new_target, &directories) else {
+                        log::warn!(
+                            "Could not find a path to {:?} for the include {} of {}.",
+                            new_target,
+                            text,
+                            document.uri
+                        );
+                        return None;
+                    };
+                    if new_path == path {
+                        return None;
+                    }
+                    Some(TextEdit {
+                        range: include_item.v_range,
+                        new_text: if quoted {
+                            format!("\"{}\"", new_path)
+                        } else {
+                            format!("<{}>", new_path)
+                        },
+                    })
+                }
+                _ => None,
+            })
+            .collect();
+        if !edits.is_empty() {
+            changes.insert((*document.uri).clone(), edits);
+        }
+    }
+    if changes.is_empty() {
+        return None;
+    }
+
+    Some(WorkspaceEdit {
+        changes: Some(changes),
+        ..Default::default()
+    })
+}
+
+/// Return the old and new paths of the renamed files or folders.
+///
+/// # Arguments
+///
+/// * `params` - [Parameters](RenameFilesParams) of the request or notification.
+pub(crate) fn renamed_paths(params: &RenameFilesParams) -> Vec<(PathBuf, PathBuf)> {
+    params
+        .files
+        .iter()
+        .filter_map(|file| {
+            let old_path = Url::parse(&file.old_uri).ok()?.to_file_path().ok()?;
+            let new_path = Url::parse(&file.new_uri).ok()?.to_file_path().ok()?;
+            Some((old_path, new_path))
+        })
+        .collect()
+}
+
+/// Return the new path of a file, or [None] if neither it nor one of its folders is renamed.
+///
+/// # Arguments
+///
+/// * `path` - Path of the file.
+/// * `renames` - Old and new paths of the renamed files or folders.
+pub(crate) fn renamed_path(path: &Path, renames: &[(PathBuf, PathBuf)]) -> Option<PathBuf> {
+    renames.iter().find_map(|(old_path, new_path)| {
+        if path == old_path {
+            return Some(new_path.clone());
+        }
+        path.strip_prefix(old_path)
+            .ok()
+            .map(|relative_path| new_path.join(relative_path))
+    })
+}
+
+/// Return the text of an include path in a document, delimiters included, e.g `<sdktools>`.
+/// Return [None] for the implicit `sourcemod` include, which has no text.
+fn include_text(document: &Document, range: lsp_types::Range) -> Option<String> {
+    if range.start.line != range.end.line {
+        return None;
+    }
+    let text: String = document
+        .line(range.start.line)?
+        .chars()
+        .skip(range.start.character as usize)
+        .take((range.end.character - range.start.character) as usize)
+        .collect();
+    if text.len() < 2 || !(text.starts_with('"') || text.starts_with('<')) {
+        return None;
+    }
+
+    Some(text)
+}
+
+/// Return the directories to look up the new location of the target of an include in. The
+/// directory the include was resolved from comes first, then the directories
+/// [Store::resolve_import] looks in from the new location of the document.
+///
+/// # Arguments
+///
+/// * `old_directories` - [Include directories](include_directories) before the rename.
+/// * `new_directories` - [Include directories](include_directories) after the rename.
+/// * `resolved_name` - Name the include was resolved with, extension included.
+/// * `old_target` - Old path of the target of the include.
+fn candidate_directories(
+    old_directories: Vec<PathBuf>,
+    mut new_directories: Vec<PathBuf>,
+    resolved_name: &str,
+    old_target: &Path,
+) -> Vec<PathBuf> {
+    if let Some(index) = old_directories
+        .iter()
+        .position(|directory| directory.join(resolved_name) == old_target)
+    {
+        if index < new_directories.len() {
+            let directory = new_directories.remove(index);
+            new_directories.insert(0, directory);
+        }
+    }
+
+    new_directories
+}
+
+/// Return the path to write in an include so that it points to the new location of its target,
+/// relative to the first directory which contains it. If none of them contains it, the path is
+/// relative to the first directory, e.g `../lib/foo`.
+///
+/// # Arguments
+///
+/// * `path` - Current path of the include, without delimiters.
+/// * `new_target` - New path of the target of the include.
+/// * `directories` - Directories to write the path relative to.
+fn new_include_path(path: &str, new_target: &Path, directories: &[PathBuf]) -> Option<String> {
+    let relative_path = match directories
+        .iter()
+        .find_map(|directory| new_target.strip_prefix(directory).ok())
+    {
+        Some(relative_path) => relative_path.to_path_buf(),
+        None => path_relative_to(new_target, directories.first()?)?,
+    };
+    let mut new_path = relative_path
+        .components()
+        .map(|component| component.as_os_str().to_string_lossy())
+        .collect::<Vec<_>>()
+        .join("/");
+
+    // Keep the implicit `.inc` extension implicit.
+    let has_extension = [".inc", ".sp", ".sma"]
+        .iter()
+        .any(|extension| path.ends_with(extension));
+    if !has_extension {
+        if let Some(stripped_path) = new_path.strip_suffix(".inc") {
+            new_path = stripped_path.to_string();
+        }
+    }
+
+    Some(new_path)
+}
+
+/// Return the path of a file relative to a directory, walking up with `..` if the directory does
+/// not contain the file. Return [None] if they don't share a root.
+///
+/// # Arguments
+///
+/// * `path` - Path of the file.
+/// * `directory` - Directory to make the path relative to.
+fn path_relative_to(path: &Path, directory: &Path) -> Option<PathBuf> {
+    let path_components: Vec<_> = path.components().collect();
+    let directory_components: Vec<_> = directory.components().collect();
+    let common_count = path_components
+        .iter()
+        .zip(directory_components.iter())
+        .take_while(|(a, b)| a == b)
+        .count();
+    if common_count == 0 {
+        return None;
+    }
+    let mut relative_path = PathBuf::new();
+    for _ in common_count..directory_components.len() {
+        relative_path.push("..");
+    }
+    for component in path_components[common_count..].iter() {
+        relative_path.push(component);
+    }
+
+    Some(relative_path)
+}
+
+/// Return the directories an include of a document is looked up in, in the order of
+/// [Store::resolve_import].
+///
+/// # Arguments
+///
+/// * `store` - [Store] of the documents.
+/// * `document_path` - Path of the document which declares the include.
+/// * `main_path` - Path of the main path which includes the document, if any.
+/// * `quoted` - Whether the include uses the `""` form.
+fn include_directories(
+    store: &Store,
+    document_path: &Path,
+    main_path: Option<&Path>,
+    quoted: bool,
+) -> Vec<PathBuf> {
+    if quoted {
+        if let Some(main_directory) = main_path.and_then(|main_path| main_path.parent()) {
+            let main_directory = main_directory.to_path_buf();
+            let include_directory = main_directory.join("include");
+            return vec![main_directory, include_directory];
+        }
+    }
+    let mut directories = vec![];
+    if let Some(document_directory) = document_path.parent() {
+        directories.push(document_directory.to_path_buf());
+        directories.push(document_directory.join("include"));
+    }
+    directories.extend(
+        store
+            .environment
+            .options
+            .includes_directories
+            .iter()
+            .cloned(),
+    );
+
+    directories
+}
+
+#[cfg(test)]
+mod tests {
+    use std::path::PathBuf;
+
+    use lsp_types::{FileRename, RenameFilesParams, TextEdit, Url};
+
+    use super::{new_include_path, provide_will_rename_files, renamed_path};
+    use crate::{providers::WorkspaceRequest, tests::fixtures::TestStore};
+
+    #[test]
+    fn renamed_path_follows_renamed_folders() {
+        let renames = vec![
+            (PathBuf::from("/a/foo.inc"), PathBuf::from("/a/bar.inc")),
+            (PathBuf::from("/a/include"), PathBuf::from("/a/lib")),
+        ];
+        assert_eq!(
+            renamed_path(&PathBuf::from("/a/foo.inc"), &renames),
+            Some(PathBuf::from("/a/bar.inc"))
+        );
+        assert_eq!(
+            renamed_path(&PathBuf::from("/a/include/baz.inc"), &renames),
+            Some(PathBuf::from("/a/lib/baz.inc"))
+        );
+        assert_eq!(renamed_path(&PathBuf::from("/a/baz.inc"), &renames), None);
+    }
+
+    #[test]
+    fn new_include_path_keeps_the_extension_form() {
+        let directories = vec![PathBuf::from("/a/include")];
+        let new_target = PathBuf::from("/a/include/bar.inc");
+        assert_eq!(
+            new_include_path("foo", &new_target, &directories),
+            Some("bar".to_string())
+        );
+        assert_eq!(
+            new_include_path("foo.inc", &new_target, &directories),
+            Some("bar.inc".to_string())
+        );
+        assert_eq!(
+            new_include_path(
+                "foo",
+                &PathBuf::from("/a/include/lib/bar.inc"),
+                &directories
+            ),
+            Some("lib/bar".to_string())
+        );
+    }
+
+    #[test]
+    fn new_include_path_uses_the_first_directory_containing_the_target() {
+        let directories = vec![PathBuf::from("/a/scripting"), PathBuf::from("/a/include")];
+        assert_eq!(
+            new_include_path("foo", &PathBuf::from("/a/include/bar.inc"), &directories),
+            Some("bar".to_string())
+        );
+    }
+
+    #[test]
+    fn new_include_path_walks_up_the_first_directory() {
+        let directories = vec![PathBuf::from("/a/scripting")];
+        assert_eq!(
+            new_include_path("foo", &PathBuf::from("/a/lib/bar.inc"), &directories),
+            Some("../lib/bar".to_string())
+        );
+        assert_eq!(
+            new_include_path("foo", &PathBuf::from("/a/lib/bar.inc"), &[]),
+            None
+        );
+    }
+
+    fn will_rename_files(fixture: &str, old_path: &str, new_path: &str) -> Vec<TextEdit> {
+        let test_store = TestStore::new(fixture).unwrap();
+        let request = WorkspaceRequest {
+            params: RenameFilesParams {
+                files: vec![FileRename {
+                    old_uri: test_store.uri(old_path).to_string(),
+                    new_uri: test_store.uri(new_path).to_string(),
+                }],
+            },
+            store: test_store.store.clone(),
+        };
+        let Some(edit) = provide_will_rename_files(request) else {
+            return vec![];
+        };
+        let mut changes = edit.changes.unwrap();
+        let main_uri: Url = (*test_store.uri("main.sp")).clone();
+        let mut edits = changes.remove(&main_uri).unwrap_or_default();
+        assert!(changes.is_empty());
+        edits.sort_by_key(|edit| edit.range.start);
+
+        edits
+    }
+
+    #[test]
+    fn will_rename_files_keeps_the_include_form() {
+        let edits = will_rename_files(
+            r#"//! main.sp
+#include <foo>
+#include "foo.inc"
+#include <other>
+//! foo.inc
+int foo;
+//! other.inc
+int other;"#,
+            "foo.inc",
+            "include/bar.inc",
+        );
+        let new_texts: Vec<&str> = edits.iter().map(|edit| edit.new_text.as_str()).collect();
+        assert_eq!(new_texts, vec!["<include/bar>", "\"include/bar.inc\""]);
+        assert_eq!(edits[0].range.start.line, 0);
+        assert_eq!(edits[1].range.start.line, 1);
+    }
+
+    #[test]
+    fn will_rename_files_follows_renamed_folders() {
+        let edits = will_rename_files(
+            r#"//! main.sp
+#include <lib/foo>
+//! lib/foo.inc
+int foo;"#,
+            "lib",
+            "include",
+        );
+        let new_texts: Vec<&str> = edits.iter().map(|edit| edit.new_text.as_str()).collect();
+        assert_eq!(new_texts, vec!["<include/foo>"]);
+    }
+}
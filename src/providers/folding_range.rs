@@ -0,0 +1,85 @@
+use std::sync::{Arc, RwLock};
+
+use lsp_types::{FoldingRange, FoldingRangeKind, FoldingRangeParams, Range};
+
+use crate::spitem::SPItem;
+
+use super::FeatureRequest;
+
+pub fn provide_folding_range(
+    request: FeatureRequest<FoldingRangeParams>,
+) -> Option<Vec<FoldingRange>> {
+    let document = request.store.documents.get(&request.uri)?;
+    let mut folding_ranges = vec![];
+    for item in document.sp_items.iter() {
+        push_item_folding_ranges(item, &mut folding_ranges);
+    }
+    folding_ranges.extend(
+        document
+            .comment_blocks
+            .iter()
+            .filter_map(|range| to_folding_range(range, Some(FoldingRangeKind::Comment), false)),
+    );
+    folding_ranges.extend(
+        document
+            .conditional_blocks
+            .iter()
+            .filter_map(|range| to_folding_range(range, Some(FoldingRangeKind::Region), false)),
+    );
+
+    Some(folding_ranges)
+}
+
+/// Push the folding ranges of an item and of its children.
+///
+/// # Arguments
+///
+/// * `item` - Item to fold.
+/// * `folding_ranges` - Vector of folding ranges to push into.
+fn push_item_folding_ranges(item: &Arc<RwLock<SPItem>>, folding_ranges: &mut Vec<FoldingRange>) {
+    let item = item.read().unwrap();
+    match &*item {
+        SPItem::Function(_) | SPItem::Enum(_) => (),
+        SPItem::Methodmap(_) | SPItem::EnumStruct(_) => {
+            if let Some(children) = item.children() {
+                for child in children.iter() {
+                    push_item_folding_ranges(child, folding_ranges);
+                }
+            }
+        }
+        _ => return,
+    }
+    folding_ranges.extend(to_folding_range(&item.v_full_range(), None, true));
+}
+
+/// Convert a [Range] to a [FoldingRange]. Return [None] if the range does not span enough lines
+/// to be folded.
+///
+/// # Arguments
+///
+/// * `range` - [Range] to convert.
+/// * `kind` - Kind of the [FoldingRange].
+/// * `keep_last_line` - Whether the last line of the range should stay visible, i.e a closing brace.
+fn to_folding_range(
+    range: &Range,
+    kind: Option<FoldingRangeKind>,
+    keep_last_line: bool,
+) -> Option<FoldingRange> {
+    let end_line = if keep_last_line {
+        range.end.line.checked_sub(1)?
+    } else {
+        range.end.line
+    };
+    if end_line <= range.start.line {
+        return None;
+    }
+
+    Some(FoldingRange {
+        start_line: range.start.line,
+        start_character: None,
+        end_line,
+        end_character: None,
+        kind,
+        collapsed_text: None,
+    })
+}
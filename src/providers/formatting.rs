@@ -0,0 +1,213 @@
+use std::ops::RangeInclusive;
+
+use lsp_types::{
+    DocumentFormattingParams, DocumentRangeFormattingParams, FormattingOptions, Position, Range,
+    TextEdit,
+};
+use rowan::TextSize;
+use tree_sitter::{Node, Parser};
+
+use crate::{line_index::LineIndex, line_index_ext::LineIndexExt, store::Store};
+
+use self::formatter::Formatter;
+
+use super::FeatureRequest;
+
+mod formatter;
+
+/// Build a [TextEdit] which replaces the whole document with its formatted text.
+pub fn provide_formatting(
+    request: FeatureRequest<DocumentFormattingParams>,
+) -> Option<Vec<TextEdit>> {
+    let text = request.store.get_text(&request.uri)?;
+    let lines = format_text(&request.store, &text, &request.params.options, None)?;
+    let line_ending = if text.contains("\r\n") { "\r\n" } else { "\n" };
+    let new_text = lines.join(line_ending);
+    if new_text == text {
+        return Some(vec![]);
+    }
+    let end = LineIndex::new(&text).line_col_lsp(TextSize::of(text.as_str()));
+
+    Some(vec![TextEdit::new(
+        Range::new(Position::new(0, 0), end),
+        new_text,
+    )])
+}
+
+/// Build the [TextEdits](TextEdit) which replace the lines of the requested range with their
+/// formatted text.
+///
+/// The opening braces are never moved when formatting a range, so that the formatted lines can be
+/// mapped to the lines of the document. Only the syntax errors of the requested lines prevent the
+/// formatting.
+pub fn provide_range_formatting(
+    request: FeatureRequest<DocumentRangeFormattingParams>,
+) -> Option<Vec<TextEdit>> {
+    let text = request.store.get_text(&request.uri)?;
+    let old_lines: Vec<&str> = text
+        .split('\n')
+        .map(|line| line.strip_suffix('\r').unwrap_or(line))
+        .collect();
+    let range = request.params.range;
+    let start = range.start.line as usize;
+    let mut end = range.end.line as usize;
+    if end > start && range.end.character == 0 {
+        end -= 1;
+    }
+    let end = end.min(old_lines.len() - 1);
+    if start > end {
+        return Some(vec![]);
+    }
+    let lines = format_text(
+        &request.store,
+        &text,
+        &request.params.options,
+        Some(start..=end),
+    )?;
+    if lines.len() != old_lines.len() {
+        log::error!("Formatted range does not map to the lines of the document.");
+        return None;
+    }
+
+    Some(
+        (start..=end)
+            .filter(|&line| lines[line] != old_lines[line])
+            .map(|line| {
+                TextEdit::new(
+                    Range::new(
+                        Position::new(line as u32, 0),
+                        Position::new(line as u32, old_lines[line].encode_utf16().count() as u32),
+                    ),
+                    lines[line].clone(),
+                )
+            })
+            .collect(),
+    )
+}
+
+/// Format a text and return its lines, or [None] if the text has syntax errors.
+///
+/// # Arguments
+///
+/// * `store` - [Store] of the server.
+/// * `text` - Text to format.
+/// * `client_options` - [FormattingOptions] sent by the client.
+/// * `lines` - Lines to format, or [None] to format the whole text. The opening braces can only
+/// be moved to another line when formatting the whole text.
+fn format_text(
+    store: &Store,
+    text: &str,
+    client_options: &FormattingOptions,
+    lines: Option<RangeInclusive<usize>>,
+) -> Option<Vec<String>> {
+    // Parse the raw text, the preprocessed text has its macros expanded.
+    let mut parser = Parser::new();
+    parser
+        .set_language(tree_sitter_sourcepawn::language())
+        .ok()?;
+    let tree = parser.parse(text, None)?;
+    let has_error = match &lines {
+        Some(lines) => has_error_in_lines(tree.root_node(), lines),
+        None => tree.root_node().has_error(),
+    };
+    if has_error {
+        log::debug!("Not formatting a document with syntax errors.");
+        return None;
+    }
+    let options = &store.environment.options.formatter;
+
+    Some(Formatter::new(text, options, client_options, lines.is_none()).format(&tree))
+}
+
+/// Return whether a node has a syntax error on some lines.
+///
+/// # Arguments
+///
+/// * `node` - [Node] to check.
+/// * `lines` - Lines to check.
+fn has_error_in_lines(node: Node, lines: &RangeInclusive<usize>) -> bool {
+    if !node.has_error()
+        || node.end_position().row < *lines.start()
+        || node.start_position().row > *lines.end()
+    {
+        return false;
+    }
+    if node.is_error() || node.is_missing() {
+        return true;
+    }
+    for child in node.children(&mut node.walk()) {
+        if has_error_in_lines(child, lines) {
+            return true;
+        }
+    }
+
+    false
+}
+
+#[cfg(test)]
+mod tests {
+    use lsp_types::{
+        DocumentRangeFormattingParams, FormattingOptions, Position, Range, TextDocumentIdentifier,
+    };
+
+    use super::provide_range_formatting;
+    use crate::{providers::FeatureRequest, tests::fixtures::TestStore};
+
+    fn range_formatting(text: &str, range: Range) -> Option<Vec<(Range, String)>> {
+        let test_store = TestStore::new(&format!("//! main.sp\n{}", text)).unwrap();
+        let uri = test_store.uri("main.sp");
+        let request = FeatureRequest {
+            params: DocumentRangeFormattingParams {
+                text_document: TextDocumentIdentifier::new((*uri).clone()),
+                range,
+                options: FormattingOptions {
+                    tab_size: 4,
+                    insert_spaces: true,
+                    ..Default::default()
+                },
+                work_done_progress_params: Default::default(),
+            },
+            store: test_store.store.clone(),
+            uri,
+        };
+
+        provide_range_formatting(request).map(|edits| {
+            edits
+                .into_iter()
+                .map(|edit| (edit.range, edit.new_text))
+                .collect()
+        })
+    }
+
+    #[test]
+    fn range_formatting_only_edits_the_range() {
+        let edits = range_formatting(
+            "void foo()\n{\nint a;\nint b;\n}\n",
+            Range::new(Position::new(2, 0), Position::new(2, 6)),
+        );
+        assert_eq!(
+            edits,
+            Some(vec![(
+                Range::new(Position::new(2, 0), Position::new(2, 6)),
+                "    int a;".to_string()
+            )])
+        );
+    }
+
+    #[test]
+    fn range_formatting_ignores_errors_outside_of_the_range() {
+        let text = "void foo()\n{\nint a;\n}\n\nvoid bar()\n{\nint b = ;\n}\n";
+        let edits = range_formatting(text, Range::new(Position::new(2, 0), Position::new(3, 0)));
+        assert_eq!(
+            edits,
+            Some(vec![(
+                Range::new(Position::new(2, 0), Position::new(2, 6)),
+                "    int a;".to_string()
+            )])
+        );
+        assert_eq!(
+            range_formatting(text, Range::new(Position::new(7, 0), Position::new(8, 0))),
+            None
+        );
+    }
+}
@@ -0,0 +1,401 @@
+use lsp_types::FormattingOptions;
+use tree_sitter::{Node, Tree};
+
+use crate::options::{BraceStyle, FormatterOptions, IndentStyle};
+
+/// Kinds of nodes which are never split into smaller tokens.
+const ATOMIC_KINDS: [&str; 3] = ["comment", "string_literal", "char_literal"];
+
+/// Keywords which can be followed by a parenthesized condition.
+const CONTROL_KEYWORDS: [&str; 4] = ["if", "for", "while", "switch"];
+
+/// Kinds of the parents of the operators which have to be surrounded by spaces.
+const OPERATOR_PARENTS: [&str; 7] = [
+    "binary_expression",
+    "assignment_expression",
+    "ternary_expression",
+    "variable_declaration",
+    "old_variable_declaration",
+    "argument_declaration",
+    "enum_entry",
+];
+
+/// Kinds of the declarations, where only `=` is an operator. `&` marks a reference and `:` a tag.
+const DECLARATION_PARENTS: [&str; 4] = [
+    "variable_declaration",
+    "old_variable_declaration",
+    "argument_declaration",
+    "enum_entry",
+];
+
+/// Kinds of the parents of the opening braces which can be moved by the formatter.
+const BRACE_PARENTS: [&str; 6] = [
+    "methodmap",
+    "enum_struct",
+    "enum",
+    "enum_entries",
+    "typeset",
+    "methodmap_property",
+];
+
+/// Token based formatter of a SourcePawn document.
+///
+/// The formatter only touches the whitespaces between tokens, the indentation of the lines
+/// and optionally the placement of the opening braces. Preprocessor directives and blank lines
+/// are kept as they are.
+pub(super) struct Formatter<'a> {
+    source: &'a str,
+    lines: Vec<&'a str>,
+    directive_rows: Vec<bool>,
+    conditional_rows: Vec<Option<Conditional>>,
+    options: &'a FormatterOptions,
+    indent_unit: String,
+    reflow_braces: bool,
+}
+
+/// Preprocessor conditional directive of a line.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+enum Conditional {
+    /// `#if`, `#ifdef` or `#ifndef`.
+    If,
+
+    /// `#else` or `#elseif`.
+    Else,
+
+    /// `#endif`.
+    EndIf,
+}
+
+impl<'a> Formatter<'a> {
+    /// Create a new [Formatter].
+    ///
+    /// # Arguments
+    ///
+    /// * `source` - Text of the document to format.
+    /// * `options` - [FormatterOptions] of the server.
+    /// * `client_options` - [FormattingOptions] sent by the client.
+    /// * `reflow_braces` - Whether the opening braces can be moved to another line.
+    pub(super) fn new(
+        source: &'a str,
+        options: &'a FormatterOptions,
+        client_options: &FormattingOptions,
+        reflow_braces: bool,
+    ) -> Self {
+        let lines: Vec<&str> = source
+            .split('\n')
+            .map(|line| line.strip_suffix('\r').unwrap_or(line))
+            .collect();
+        let mut directive_rows = Vec::with_capacity(lines.len());
+        let mut conditional_rows = Vec::with_capacity(lines.len());
+        let mut continued = false;
+        for line in lines.iter() {
+            let is_directive = continued || line.trim_start().starts_with('#');
+            conditional_rows.push(if continued { None } else { conditional(line) });
+            continued = is_directive && line.trim_end().ends_with('\\');
+            directive_rows.push(is_directive);
+        }
+        let indent_unit = match options.indent_style {
+            Some(IndentStyle::Tab) => "\t".to_string(),
+            Some(IndentStyle::Space) => " ".repeat(options.indent_width as usize),
+            None => {
+                if client_options.insert_spaces {
+                    " ".repeat(client_options.tab_size as usize)
+                } else {
+                    "\t".to_string()
+                }
+            }
+        };
+
+        Self {
+            source,
+            lines,
+            directive_rows,
+            conditional_rows,
+            options,
+            indent_unit,
+            reflow_braces,
+        }
+    }
+
+    /// Format the document and return its lines, without line endings.
+    ///
+    /// When the braces are not reflowed, the returned lines map 1:1 to the lines of the source.
+    ///
+    /// # Arguments
+    ///
+    /// * `tree` - Syntax tree of the source.
+    pub(super) fn format(&self, tree: &Tree) -> Vec<String> {
+        let mut leaves = vec![];
+        collect_leaves(tree.root_node(), &mut leaves);
+
+        let mut output: Vec<String> = vec![];
+        let mut current: Option<String> = None;
+        let mut next_row = 0;
+        let mut depth = 0usize;
+        let mut paren_depth = 0usize;
+        let mut prev: Option<Node> = None;
+        // Depth before each open `#if`, and depth at the end of its first branch.
+        let mut conditionals: Vec<(usize, Option<usize>)> = vec![];
+        let mut conditional_row = 0;
+
+        for leaf in leaves
+            .into_iter()
+            .filter(|leaf| !self.directive_rows[leaf.start_position().row])
+        {
+            let row = leaf.start_position().row;
+            // Only count the braces of one branch of the conditionals, which usually all open
+            // or close the same block.
+            while conditional_row < row {
+                match self.conditional_rows[conditional_row] {
+                    Some(Conditional::If) => conditionals.push((depth, None)),
+                    Some(Conditional::Else) => {
+                        if let Some((start_depth, end_depth)) = conditionals.last_mut() {
+                            end_depth.get_or_insert(depth);
+                            depth = *start_depth;
+                        }
+                    }
+                    Some(Conditional::EndIf) => {
+                        if let Some((_, Some(end_depth))) = conditionals.pop() {
+                            depth = end_depth;
+                        }
+                    }
+                    None => (),
+                }
+                conditional_row += 1;
+            }
+            let mut new_line = match prev {
+                Some(prev) => row > prev.end_position().row,
+                None => true,
+            };
+            if let Some(prev) = prev {
+                if self.reflow_braces && is_reflowable_brace(&leaf) {
+                    match self.options.brace_style {
+                        BraceStyle::SameLine => {
+                            if new_line
+                                && row == prev.end_position().row + 1
+                                && prev.kind() != "comment"
+                            {
+                                new_line = false;
+                            }
+                        }
+                        BraceStyle::NextLine => new_line = true,
+                        BraceStyle::Preserve => (),
+                    }
+                }
+            }
+
+            let text = &self.source[leaf.byte_range()];
+            match leaf.kind() {
+                "}" => depth = depth.saturating_sub(1),
+                ")" | "]" => paren_depth = paren_depth.saturating_sub(1),
+                _ => (),
+            }
+            let appended = match (prev, current.as_mut()) {
+                (Some(prev), Some(line)) if !new_line => {
+                    line.push_str(&self.spacing(&prev, &leaf));
+                    line.push_str(text);
+                    true
+                }
+                _ => false,
+            };
+            if !appended {
+                if let Some(line) = current.take() {
+                    push_lines(&mut output, &line);
+                }
+                while next_row < row {
+                    output.push(self.lines[next_row].trim_end().to_string());
+                    next_row += 1;
+                }
+                let mut line = self
+                    .indent_unit
+                    .repeat(depth + usize::from(paren_depth > 0));
+                line.push_str(text);
+                current = Some(line);
+            }
+            match leaf.kind() {
+                "{" => depth += 1,
+                "(" | "[" => paren_depth += 1,
+                _ => (),
+            }
+            next_row = next_row.max(leaf.end_position().row + 1);
+            prev = Some(leaf);
+        }
+        if let Some(line) = current.take() {
+            push_lines(&mut output, &line);
+        }
+        while next_row < self.lines.len() {
+            output.push(self.lines[next_row].trim_end().to_string());
+            next_row += 1;
+        }
+
+        output
+    }
+
+    /// Return the whitespaces to insert between two consecutive tokens of a line.
+    ///
+    /// # Arguments
+    ///
+    /// * `prev` - Previous token of the line.
+    /// * `leaf` - Token to insert.
+    fn spacing(&self, prev: &Node, leaf: &Node) -> String {
+        let gap = &self.source[prev.end_byte()..leaf.start_byte()];
+        if prev.kind() == "comment" || leaf.kind() == "comment" {
+            return gap.to_string();
+        }
+        let space = |enabled: bool| if enabled { " " } else { "" }.to_string();
+        if matches!(leaf.kind(), "," | ";") {
+            return String::new();
+        }
+        if prev.kind() == "," {
+            return space(self.options.space_after_comma);
+        }
+        if !prev.is_named() && CONTROL_KEYWORDS.contains(&prev.kind()) && leaf.kind() == "(" {
+            return space(self.options.space_after_keyword);
+        }
+        if is_operator(prev) || is_operator(leaf) {
+            return space(self.options.space_around_operators);
+        }
+
+        space(!gap.is_empty())
+    }
+}
+
+/// Collect the tokens of a tree, in order.
+///
+/// # Arguments
+///
+/// * `node` - Root [Node] of the tree.
+/// * `leaves` - Vector to push the tokens into.
+fn collect_leaves<'tree>(node: Node<'tree>, leaves: &mut Vec<Node<'tree>>) {
+    if node.child_count() == 0 || ATOMIC_KINDS.contains(&node.kind()) {
+        if node.start_byte() < node.end_byte() {
+            leaves.push(node);
+        }
+        return;
+    }
+    let mut cursor = node.walk();
+    for child in node.children(&mut cursor) {
+        collect_leaves(child, leaves);
+    }
+}
+
+/// Push a formatted line, which may contain line breaks, to the output.
+fn push_lines(output: &mut Vec<String>, line: &str) {
+    output.extend(line.split('\n').map(|line| line.trim_end().to_string()));
+}
+
+/// Return the conditional directive of a line, if any.
+fn conditional(line: &str) -> Option<Conditional> {
+    let directive = line.trim_start().strip_prefix('#')?.trim_start();
+    let name: String = directive
+        .chars()
+        .take_while(|c| c.is_ascii_alphabetic())
+        .collect();
+    match name.as_str() {
+        "if" | "ifdef" | "ifndef" => Some(Conditional::If),
+        "else" | "elseif" => Some(Conditional::Else),
+        "endif" => Some(Conditional::EndIf),
+        _ => None,
+    }
+}
+
+fn is_operator(node: &Node) -> bool {
+    if node.is_named() || matches!(node.kind(), "(" | ")" | "[" | "]" | "," | ";") {
+        return false;
+    }
+    let Some(parent) = node.parent() else {
+        return false;
+    };
+    if DECLARATION_PARENTS.contains(&parent.kind()) {
+        return node.kind() == "=";
+    }
+
+    OPERATOR_PARENTS.contains(&parent.kind())
+}
+
+fn is_reflowable_brace(node: &Node) -> bool {
+    if node.kind() != "{" {
+        return false;
+    }
+    let Some(parent) = node.parent() else {
+        return false;
+    };
+    if parent.kind() == "block" {
+        // Do not move the braces of nested blocks.
+        return parent
+            .parent()
+            .map_or(true, |grand_parent| grand_parent.kind() != "block");
+    }
+
+    BRACE_PARENTS.contains(&parent.kind())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn format(source: &str, options: &FormatterOptions, reflow_braces: bool) -> String {
+        let mut parser = tree_sitter::Parser::new();
+        parser
+            .set_language(tree_sitter_sourcepawn::language())
+            .unwrap();
+        let tree = parser.parse(source, None).unwrap();
+        let client_options = FormattingOptions {
+            tab_size: 4,
+            insert_spaces: true,
+            ..Default::default()
+        };
+        Formatter::new(source, options, &client_options, reflow_braces)
+            .format(&tree)
+            .join("\n")
+    }
+
+    #[test]
+    fn indentation_and_spacing() {
+        let source = "int foo(int a,int b)\n{\nif(a==b)\n{\nreturn a+b;\n}\nreturn 0;\n}\n";
+        let expected =
+            "int foo(int a, int b)\n{\n    if (a == b)\n    {\n        return a + b;\n    }\n    return 0;\n}\n";
+        assert_eq!(format(source, &FormatterOptions::default(), true), expected);
+    }
+
+    #[test]
+    fn same_line_braces() {
+        let source = "void foo()\n{\n    bar();\n}\n";
+        let options = FormatterOptions {
+            brace_style: BraceStyle::SameLine,
+            ..Default::default()
+        };
+        assert_eq!(
+            format(source, &options, true),
+            "void foo() {\n    bar();\n}\n"
+        );
+    }
+
+    #[test]
+    fn directives_are_preserved() {
+        let source = "#define FOO(%1)   %1 +   1\nint a=FOO(1);\n";
+        assert_eq!(
+            format(source, &FormatterOptions::default(), false),
+            "#define FOO(%1)   %1 +   1\nint a = FOO(1);\n"
+        );
+    }
+
+    #[test]
+    fn references_and_tags_are_not_operators() {
+        let source = "void foo(int &a,Float:b=1.0)\n{\n}\n";
+        assert_eq!(
+            format(source, &FormatterOptions::default(), false),
+            "void foo(int &a, Float:b = 1.0)\n{\n}\n"
+        );
+    }
+
+    #[test]
+    fn conditional_braces_are_counted_once() {
+        let source =
+            "#if defined FOO\nvoid foo(int a)\n{\n#else\nvoid foo()\n{\n#endif\nbar();\n}\nint b;\n";
+        assert_eq!(
+            format(source, &FormatterOptions::default(), false),
+            "#if defined FOO\nvoid foo(int a)\n{\n#else\nvoid foo()\n{\n#endif\n    bar();\n}\nint b;\n"
+        );
+    }
+}
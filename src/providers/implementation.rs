@@ -0,0 +1,155 @@
+use lsp_types::request::{GotoImplementationParams, GotoImplementationResponse};
+
+use crate::spitem::{function_item::FunctionDefinitionType, SPItem};
+
+use super::FeatureRequest;
+
+/// Build the implementations of the forward, typedef or typeset under the cursor, which are the
+/// public functions with the same name as the forward, or with a signature of the typedef.
+///
+/// # Arguments
+///
+/// * `request` - Implementation request object [FeatureRequest<GotoImplementationParams>].
+pub fn provide_implementation(
+    request: FeatureRequest<GotoImplementationParams>,
+) -> Option<GotoImplementationResponse> {
+    let items = &request.store.get_items_from_position(
+        request.params.text_document_position_params.position,
+        request
+            .params
+            .text_document_position_params
+            .text_document
+            .uri
+            .clone(),
+    );
+    if items.is_empty() {
+        return None;
+    }
+    let all_items = request.store.get_all_items(false).0;
+    let public_functions: Vec<_> = all_items
+        .iter()
+        .filter(|item| match &*item.read().unwrap() {
+            SPItem::Function(function_item) => {
+                function_item.is_public()
+                    && function_item.definition_type == FunctionDefinitionType::None
+            }
+            _ => false,
+        })
+        .collect();
+    let mut implementations = vec![];
+    for item in items.iter() {
+        let item = item.read().unwrap();
+        for function in public_functions.iter() {
+            let function = function.read().unwrap();
+            let function_item = match &*function {
+                SPItem::Function(function_item) => function_item,
+                _ => continue,
+            };
+            let implements = match &*item {
+                SPItem::Function(forward_item) => {
+                    forward_item.definition_type == FunctionDefinitionType::Forward
+                        && forward_item.name == function_item.name
+                }
+                SPItem::Typedef(typedef_item) => typedef_item.is_implemented_by(function_item),
+                SPItem::Typeset(typeset_item) => {
+                    typeset_item
+                        .children
+                        .iter()
+                        .any(|child| match &*child.read().unwrap() {
+                            SPItem::Typedef(typedef_item) => {
+                                typedef_item.is_implemented_by(function_item)
+                            }
+                            _ => false,
+                        })
+                }
+                _ => false,
+            };
+            if implements {
+                implementations.extend(function.to_definition(&request.params));
+            }
+        }
+    }
+
+    Some(GotoImplementationResponse::Link(implementations))
+}
+
+#[cfg(test)]
+mod tests {
+    use std::sync::Arc;
+
+    use lsp_types::{request::GotoImplementationResponse, GotoDefinitionParams, Location};
+
+    use super::provide_implementation;
+    use crate::{providers::FeatureRequest, tests::fixtures::TestStore};
+
+    fn implementations(test_store: &TestStore) -> Vec<Location> {
+        let cursor = test_store.cursor().unwrap();
+        let uri = Arc::new(cursor.text_document.uri.clone());
+        let request = FeatureRequest {
+            params: GotoDefinitionParams {
+                text_document_position_params: cursor,
+                work_done_progress_params: Default::default(),
+                partial_result_params: Default::default(),
+            },
+            store: test_store.store.clone(),
+            uri,
+        };
+
+        match provide_implementation(request) {
+            Some(GotoImplementationResponse::Link(links)) => links
+                .into_iter()
+                .map(|link| Location::new(link.target_uri, link.target_selection_range))
+                .collect(),
+            _ => vec![],
+        }
+    }
+
+    #[test]
+    fn public_function_of_a_forward() {
+        let test_store = TestStore::new(
+            r#"
+//! foo.inc
+forward void OnFoo(int a);
+              |
+
+//! main.sp
+public void OnFoo(int a)
+            ^^^^^
+{
+}
+
+void OnBar(int a)
+{
+}
+"#,
+        )
+        .unwrap();
+        assert_eq!(implementations(&test_store), test_store.locations());
+    }
+
+    #[test]
+    fn public_functions_of_a_typedef() {
+        let test_store = TestStore::new(
+            r#"
+//! main.sp
+typedef Callback = function void (int client);
+         |
+
+public void OnClient(int client)
+            ^^^^^^^^
+{
+}
+
+public void OnFloat(float client)
+{
+}
+
+public int OnInt(int client)
+{
+}
+"#,
+        )
+        .unwrap();
+        assert_eq!(implementations(&test_store), test_store.locations());
+    }
+}
@@ -0,0 +1,341 @@
+use std::sync::{Arc, RwLock};
+
+use fxhash::FxHashMap;
+use lsp_types::{InlayHint, InlayHintKind, InlayHintLabel, InlayHintParams, Position, Range, Url};
+use tree_sitter::{Node, Point};
+
+use crate::{
+    document::Document,
+    line_index::{LineCol, LineColUtf16, LineIndex},
+    spitem::SPItem,
+    store::Store,
+    utils::{point_to_lsp_position, range_contains_pos, ts_range_to_lsp_range},
+};
+
+use super::FeatureRequest;
+
+pub fn provide_inlay_hints(request: FeatureRequest<InlayHintParams>) -> Option<Vec<InlayHint>> {
+    let options = &request.store.environment.options.inlay_hints;
+    if !options.parameter_names && !options.variable_types {
+        return Some(vec![]);
+    }
+    let document = request.store.documents.get(&request.uri)?;
+    let tree = document.tree.as_ref()?;
+    let functions = called_functions(&request.store, &request.uri);
+
+    let mut hints = vec![];
+    if options.parameter_names {
+        push_parameter_hints(document, tree.root_node(), &functions, &mut hints);
+    }
+    if options.variable_types {
+        push_variable_hints(document, tree.root_node(), &functions, &mut hints);
+    }
+    // The positions of the items are byte columns, while the client counts UTF-16 code units.
+    let line_index = LineIndex::new(&document.text);
+    let range = Range::new(
+        utf16_to_utf8(&line_index, request.params.range.start),
+        utf16_to_utf8(&line_index, request.params.range.end),
+    );
+    hints.retain(|hint| range_contains_pos(range, hint.position));
+    for hint in hints.iter_mut() {
+        let LineColUtf16 { line, col } = line_index.to_utf16(LineCol {
+            line: hint.position.line,
+            col: hint.position.character,
+        });
+        hint.position = Position::new(line, col);
+    }
+
+    Some(hints)
+}
+
+/// Convert a [Position] of the client, in UTF-16 code units, to a [Position] in bytes.
+///
+/// # Arguments
+///
+/// * `line_index` - [LineIndex] of the text of the document.
+/// * `position` - [Position] to convert.
+fn utf16_to_utf8(line_index: &LineIndex, position: Position) -> Position {
+    let LineCol { line, col } = line_index.to_utf8(LineColUtf16 {
+        line: position.line,
+        col: position.character,
+    });
+
+    Position::new(line, col)
+}
+
+/// Map the start of the references of the functions in a document to the called functions.
+/// The references to a methodmap are mapped to its constructor.
+///
+/// # Arguments
+///
+/// * `store` - [Store] of the server.
+/// * `uri` - [Uri](Url) of the document.
+fn called_functions(store: &Store, uri: &Url) -> FxHashMap<(u32, u32), Arc<RwLock<SPItem>>> {
+    let mut functions = FxHashMap::default();
+    for item in store.get_all_items(true).0.iter() {
+        let item_lock = item.read().unwrap();
+        let function = match &*item_lock {
+            SPItem::Function(_) => item.clone(),
+            SPItem::Methodmap(_) => match item_lock.ctor() {
+                Some(ctor) => ctor,
+                None => continue,
+            },
+            _ => continue,
+        };
+        if let Some(references) = item_lock.references() {
+            for reference in references.iter().filter(|reference| *reference.uri == *uri) {
+                functions.insert(
+                    (reference.range.start.line, reference.range.start.character),
+                    function.clone(),
+                );
+            }
+        }
+    }
+
+    functions
+}
+
+/// Key of a [Node] in the map returned by [called_functions].
+fn position_key(node: &Node) -> (u32, u32) {
+    let position = point_to_lsp_position(&node.start_position());
+
+    (position.line, position.character)
+}
+
+/// Push a hint with the name of the parameter before each argument of the calls of a tree.
+///
+/// # Arguments
+///
+/// * `document` - [Document] of the request.
+/// * `node` - Root [Node] of the tree to walk.
+/// * `functions` - Called functions, by position of their reference.
+/// * `hints` - Vector of hints to push into.
+fn push_parameter_hints(
+    document: &Document,
+    node: Node,
+    functions: &FxHashMap<(u32, u32), Arc<RwLock<SPItem>>>,
+    hints: &mut Vec<InlayHint>,
+) {
+    if node.kind() == "call_expression" {
+        let _ = push_call_hints(document, node, functions, hints);
+    }
+    let mut cursor = node.walk();
+    for child in node.children(&mut cursor) {
+        push_parameter_hints(document, child, functions, hints);
+    }
+}
+
+fn push_call_hints(
+    document: &Document,
+    node: Node,
+    functions: &FxHashMap<(u32, u32), Arc<RwLock<SPItem>>>,
+    hints: &mut Vec<InlayHint>,
+) -> Option<()> {
+    let mut function_node = node.child_by_field_name("function")?;
+    if let Some(field_node) = function_node.child_by_field_name("field") {
+        function_node = field_node;
+    }
+    let function = functions.get(&position_key(&function_node))?;
+    let SPItem::Function(function_item) = &*function.read().unwrap() else {
+        return None;
+    };
+    let arguments_node = node.child_by_field_name("arguments")?;
+    let mut cursor = arguments_node.walk();
+    let arguments = arguments_node
+        .named_children(&mut cursor)
+        .filter(|argument| argument.kind() != "comment");
+    for (argument, param) in arguments.zip(function_item.params.iter()) {
+        if argument.kind().contains("named") {
+            // Named arguments break the order of the parameters.
+            break;
+        }
+        let param = param.read().unwrap();
+        let argument_text = argument
+            .utf8_text(document.preprocessed_text.as_bytes())
+            .ok()?;
+        if argument_text == param.name {
+            continue;
+        }
+        hints.push(InlayHint {
+            position: document
+                .build_v_range(&ts_range_to_lsp_range(&argument.range()))
+                .start,
+            label: InlayHintLabel::String(format!("{}:", param.name)),
+            kind: Some(InlayHintKind::PARAMETER),
+            text_edits: None,
+            tooltip: None,
+            padding_left: None,
+            padding_right: Some(true),
+            data: None,
+        });
+    }
+
+    Some(())
+}
+
+/// Push a hint with the inferred type of the variables of a document declared without a tag.
+///
+/// # Arguments
+///
+/// * `document` - [Document] of the request.
+/// * `root_node` - Root [Node] of the tree of the document.
+/// * `functions` - Called functions, by position of their reference.
+/// * `hints` - Vector of hints to push into.
+fn push_variable_hints(
+    document: &Document,
+    root_node: Node,
+    functions: &FxHashMap<(u32, u32), Arc<RwLock<SPItem>>>,
+    hints: &mut Vec<InlayHint>,
+) {
+    for item in document.get_sp_items_flat().iter() {
+        let SPItem::Variable(variable_item) = &*item.read().unwrap() else {
+            continue;
+        };
+        if !variable_item.type_.is_empty() || *variable_item.uri != *document.uri {
+            continue;
+        }
+        let Some(type_) = infer_variable_type(document, root_node, &variable_item.range, functions)
+        else {
+            continue;
+        };
+        hints.push(InlayHint {
+            position: variable_item.v_range.end,
+            label: InlayHintLabel::String(format!(": {}", type_)),
+            kind: Some(InlayHintKind::TYPE),
+            text_edits: None,
+            tooltip: None,
+            padding_left: None,
+            padding_right: None,
+            data: None,
+        });
+    }
+}
+
+/// Infer the type of a variable declared without a tag from its initial value. Untagged
+/// variables without an initial value are integers.
+///
+/// Return [None] if the variable is not a variable declaration, has an explicit tag or has an
+/// initial value whose type is not obvious.
+///
+/// # Arguments
+///
+/// * `document` - [Document] of the variable.
+/// * `root_node` - Root [Node] of the tree of the document.
+/// * `range` - Range of the name of the variable, built from the tree of the document.
+/// * `functions` - Called functions, by position of their reference.
+fn infer_variable_type(
+    document: &Document,
+    root_node: Node,
+    range: &Range,
+    functions: &FxHashMap<(u32, u32), Arc<RwLock<SPItem>>>,
+) -> Option<String> {
+    // The range comes from the tree, its columns are already byte offsets.
+    let name_node = root_node.descendant_for_point_range(
+        Point::new(range.start.line as usize, range.start.character as usize),
+        Point::new(range.end.line as usize, range.end.character as usize),
+    )?;
+    let declaration_node = name_node.parent()?;
+    if !matches!(
+        declaration_node.kind(),
+        "variable_declaration" | "old_variable_declaration"
+    ) || declaration_node.child_by_field_name("type").is_some()
+    {
+        return None;
+    }
+    let mut cursor = declaration_node.walk();
+    let value_node = declaration_node
+        .children(&mut cursor)
+        .skip_while(|child| child.kind() != "=")
+        .find(|child| child.is_named());
+    let type_ = match value_node.map(|node| (node.kind(), node)) {
+        None | Some(("int_literal", _)) => "int".to_string(),
+        Some(("float_literal", _)) => "float".to_string(),
+        Some(("char_literal" | "string_literal", _)) => "char".to_string(),
+        Some(("bool_literal", _)) => "bool".to_string(),
+        Some(("call_expression", node)) => {
+            let mut function_node = node.child_by_field_name("function")?;
+            if let Some(field_node) = function_node.child_by_field_name("field") {
+                function_node = field_node;
+            }
+            match functions.get(&position_key(&function_node)) {
+                Some(function) => match &*function.read().unwrap() {
+                    SPItem::Function(function_item) if !function_item.type_.is_empty() => {
+                        function_item.type_.clone()
+                    }
+                    _ => "int".to_string(),
+                },
+                None => "int".to_string(),
+            }
+        }
+        _ => return None,
+    };
+    log::trace!(
+        "Inferred type {} for {:?} in {}",
+        type_,
+        range,
+        document.uri
+    );
+
+    Some(type_)
+}
+
+#[cfg(test)]
+mod tests {
+    use lsp_types::{InlayHintLabel, InlayHintParams, Position, Range, TextDocumentIdentifier};
+
+    use super::provide_inlay_hints;
+    use crate::{providers::FeatureRequest, tests::fixtures::TestStore};
+
+    fn inlay_hints(text: &str) -> Vec<(Position, String)> {
+        let test_store = TestStore::new(&format!("//! main.sp\n{}", text)).unwrap();
+        let uri = test_store.uri("main.sp");
+        let request = FeatureRequest {
+            params: InlayHintParams {
+                text_document: TextDocumentIdentifier::new((*uri).clone()),
+                range: Range::new(Position::new(0, 0), Position::new(u32::MAX, 0)),
+                work_done_progress_params: Default::default(),
+            },
+            store: test_store.store.clone(),
+            uri,
+        };
+
+        let mut hints: Vec<(Position, String)> = provide_inlay_hints(request)
+            .unwrap()
+            .into_iter()
+            .map(|hint| match hint.label {
+                InlayHintLabel::String(label) => (hint.position, label),
+                InlayHintLabel::LabelParts(_) => unreachable!(),
+            })
+            .collect();
+        hints.sort_by_key(|(position, _)| *position);
+
+        hints
+    }
+
+    #[test]
+    fn parameter_names() {
+        assert_eq!(
+            inlay_hints("void foo(int a, int b) {}\nvoid bar() { int b; foo(1, b); }"),
+            vec![(Position::new(1, 24), "a:".to_string())]
+        );
+    }
+
+    #[test]
+    fn variable_types() {
+        assert_eq!(
+            inlay_hints("new foo = 1.0;\nnew bar;\nnew baz = foo * 2;\nint qux = 1;"),
+            vec![
+                (Position::new(0, 7), ": float".to_string()),
+                (Position::new(1, 7), ": int".to_string()),
+            ]
+        );
+    }
+
+    #[test]
+    fn variable_types_utf16_position() {
+        assert_eq!(
+            inlay_hints("char s[] = \"é\"; new foo = 1.0;"),
+            vec![(Position::new(0, 23), ": float".to_string())]
+        );
+    }
+}
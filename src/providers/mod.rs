@@ -1,12 +1,23 @@
 pub mod call_hierarchy;
+pub mod code_action;
 pub mod completion;
+pub mod declaration;
 pub mod definition;
+pub mod document_highlight;
 pub mod document_symbol;
+pub mod file_rename;
+pub mod folding_range;
+pub mod formatting;
 pub mod hover;
+pub mod implementation;
+pub mod inlay_hint;
 pub mod reference;
 pub mod rename;
 pub mod semantic_tokens;
 pub mod signature_help;
+pub mod type_definition;
+pub mod type_hierarchy;
+pub mod workspace_symbol;
 
 use std::sync::Arc;
 
@@ -20,3 +31,10 @@ pub struct FeatureRequest<P> {
     pub store: Store,
     pub uri: Arc<Url>,
 }
+
+/// Request which is not tied to a document, and which is not scoped to a main path.
+#[derive(Clone)]
+pub struct WorkspaceRequest<P> {
+    pub params: P,
+    pub store: Store,
+}
@@ -1,36 +1,181 @@
-use std::collections::HashMap;
+use std::{
+    collections::HashMap,
+    sync::{Arc, RwLock},
+};
 
-use lsp_types::{RenameParams, TextEdit, WorkspaceEdit};
+use lsp_types::{
+    Position, PrepareRenameResponse, Range, RenameParams, TextDocumentPositionParams, TextEdit,
+    Url, WorkspaceEdit,
+};
+
+use crate::{spitem::SPItem, store::Store, utils::range_contains_pos};
 
 use super::FeatureRequest;
 
-pub fn provide_rename(request: FeatureRequest<RenameParams>) -> Option<WorkspaceEdit> {
-    let items = &request.store.get_items_from_position(
+/// Keywords of SourcePawn, which can't be used as names.
+const KEYWORDS: &[&str] = &[
+    "acquire",
+    "any",
+    "as",
+    "assert",
+    "bool",
+    "break",
+    "builtin",
+    "case",
+    "cast_to",
+    "catch",
+    "cellsof",
+    "char",
+    "const",
+    "continue",
+    "decl",
+    "default",
+    "defined",
+    "delete",
+    "do",
+    "double",
+    "else",
+    "enum",
+    "exit",
+    "explicit",
+    "false",
+    "finally",
+    "float",
+    "for",
+    "foreach",
+    "forward",
+    "funcenum",
+    "functag",
+    "function",
+    "goto",
+    "if",
+    "implicit",
+    "import",
+    "in",
+    "int",
+    "int16",
+    "int32",
+    "int64",
+    "int8",
+    "interface",
+    "intn",
+    "let",
+    "methodmap",
+    "namespace",
+    "native",
+    "new",
+    "null",
+    "object",
+    "operator",
+    "package",
+    "private",
+    "property",
+    "protected",
+    "public",
+    "readonly",
+    "return",
+    "sealed",
+    "sizeof",
+    "static",
+    "stock",
+    "struct",
+    "switch",
+    "this",
+    "throw",
+    "true",
+    "try",
+    "typedef",
+    "typeof",
+    "typeset",
+    "uint16",
+    "uint32",
+    "uint64",
+    "uint8",
+    "uintn",
+    "union",
+    "using",
+    "var",
+    "variant",
+    "view_as",
+    "virtual",
+    "void",
+    "volatile",
+    "while",
+    "with",
+];
+
+/// Return the exact range of the occurrence of the item under the cursor, or an error if that
+/// item can't be renamed.
+///
+/// # Arguments
+///
+/// * `request` - Prepare rename request object [FeatureRequest<TextDocumentPositionParams>].
+pub fn provide_prepare_rename(
+    request: FeatureRequest<TextDocumentPositionParams>,
+) -> Result<Option<PrepareRenameResponse>, String> {
+    let Some(item) = renamed_item(
+        &request.store,
+        request.params.position,
+        &request.params.text_document.uri,
+    ) else {
+        return Ok(None);
+    };
+    check_renamable(&request.store, &item)?;
+    let item = item.read().unwrap();
+
+    Ok(occurrence_range(
+        &item,
+        &request.params.text_document.uri,
+        request.params.position,
+    )
+    .map(PrepareRenameResponse::Range))
+}
+
+pub fn provide_rename(
+    request: FeatureRequest<RenameParams>,
+) -> Result<Option<WorkspaceEdit>, String> {
+    let Some(item) = renamed_item(
+        &request.store,
         request.params.text_document_position.position,
-        request
-            .params
-            .text_document_position
-            .text_document
-            .uri
-            .clone(),
-    );
-    if items.len() != 1 {
-        return None;
+        &request.params.text_document_position.text_document.uri,
+    ) else {
+        return Ok(None);
+    };
+    check_renamable(&request.store, &item)?;
+    let new_name = &request.params.new_name;
+    if KEYWORDS.contains(&new_name.as_str()) {
+        return Err(format!("`{}` is a keyword.", new_name));
     }
-    let item = items[0].read().unwrap();
+    if !is_identifier(new_name) {
+        return Err(format!("`{}` is not a valid identifier.", new_name));
+    }
+    if let Some(conflict) = scope_items(&request.store, &item)
+        .iter()
+        .chain(reference_scope_items(&request.store, &item).iter())
+        .find(|other| !Arc::ptr_eq(other, &item) && other.read().unwrap().name() == *new_name)
+    {
+        let conflict = conflict.read().unwrap();
+        return Err(format!(
+            "`{}` is already declared in {}:{}.",
+            new_name,
+            conflict.uri(),
+            conflict.v_range().start.line + 1
+        ));
+    }
+    let item = item.read().unwrap();
 
     let mut changes = HashMap::new();
     changes.insert(
         (*item.uri()).clone(),
         vec![TextEdit {
             range: item.v_range(),
-            new_text: request.params.new_name.clone(),
+            new_text: new_name.clone(),
         }],
     );
-    for reference in item.references()? {
+    for reference in item.references().into_iter().flatten() {
         let edit = TextEdit {
             range: reference.v_range,
-            new_text: request.params.new_name.clone(),
+            new_text: new_name.clone(),
         };
         if let Some(uri_changes) = changes.get_mut(&reference.uri) {
             uri_changes.push(edit)
@@ -39,8 +184,176 @@ pub fn provide_rename(request: FeatureRequest<RenameParams>) -> Option<Workspace
         }
     }
 
-    Some(WorkspaceEdit {
+    Ok(Some(WorkspaceEdit {
         changes: Some(changes),
         ..Default::default()
-    })
+    }))
+}
+
+/// Return the item under the cursor, or [None] if there is none or if it is ambiguous.
+fn renamed_item(store: &Store, position: Position, uri: &Url) -> Option<Arc<RwLock<SPItem>>> {
+    let items = store.get_items_from_position(position, uri.clone());
+    if items.len() != 1 {
+        return None;
+    }
+
+    items.into_iter().next()
+}
+
+/// Return an error if an item can't be renamed, because it is an include or because it is
+/// declared in an include directory.
+fn check_renamable(store: &Store, item: &Arc<RwLock<SPItem>>) -> Result<(), String> {
+    let item = item.read().unwrap();
+    if matches!(&*item, SPItem::Include(_)) {
+        return Err("Includes can't be renamed.".to_string());
+    }
+    if store.is_in_includes_directories(&item.uri()) {
+        return Err(format!(
+            "`{}` is declared in an include directory and can't be renamed.",
+            item.name()
+        ));
+    }
+
+    Ok(())
+}
+
+/// Return the user visible range of the declaration or of the reference of an item which
+/// contains a position.
+fn occurrence_range(item: &SPItem, uri: &Url, position: Position) -> Option<Range> {
+    if *item.uri() == *uri && range_contains_pos(item.v_range(), position) {
+        return Some(item.v_range());
+    }
+
+    item.references()?
+        .iter()
+        .find(|reference| *reference.uri == *uri && range_contains_pos(reference.v_range, position))
+        .map(|reference| reference.v_range)
+}
+
+/// Return the items declared in the same scope as an item: the children of its parent, or the
+/// first level items and the enum members in the scope of its main path.
+fn scope_items(store: &Store, item: &Arc<RwLock<SPItem>>) -> Vec<Arc<RwLock<SPItem>>> {
+    let item = item.read().unwrap();
+    if let Some(parent) = item.parent() {
+        let parent = parent.read().unwrap();
+        // Enum members are global.
+        if !matches!(&*parent, SPItem::Enum(_)) {
+            return parent.children().cloned().unwrap_or_default();
+        }
+    }
+    let mut items = vec![];
+    for other in store
+        .get_all_items_of_main(store.main_uri_of(&item.uri()), false)
+        .0
+    {
+        match &*other.read().unwrap() {
+            SPItem::Include(_) => continue,
+            SPItem::Enum(enum_item) => items.extend(enum_item.children.iter().cloned()),
+            _ => (),
+        }
+        items.push(other);
+    }
+
+    items
+}
+
+/// Return the items declared in the functions which contain a reference to an item, as they
+/// would shadow the item once renamed to their name.
+fn reference_scope_items(store: &Store, item: &Arc<RwLock<SPItem>>) -> Vec<Arc<RwLock<SPItem>>> {
+    let (uri, references) = {
+        let item = item.read().unwrap();
+        (item.uri(), item.references().cloned().unwrap_or_default())
+    };
+    let mut items = vec![];
+    for other in store.get_all_items_of_main(store.main_uri_of(&uri), true).0 {
+        let other = other.read().unwrap();
+        let SPItem::Function(function_item) = &*other else {
+            continue;
+        };
+        if references.iter().any(|reference| {
+            reference.uri == function_item.uri
+                && range_contains_pos(function_item.v_full_range, reference.v_range.start)
+        }) {
+            items.extend(function_item.children.iter().cloned());
+        }
+    }
+
+    items
+}
+
+/// Check if a name is a valid SourcePawn identifier.
+fn is_identifier(name: &str) -> bool {
+    let mut chars = name.chars();
+    match chars.next() {
+        Some(first) if first.is_ascii_alphabetic() || first == '_' => {
+            chars.all(|c| c.is_ascii_alphanumeric() || c == '_')
+        }
+        _ => false,
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use std::sync::Arc;
+
+    use lsp_types::{RenameParams, WorkspaceEdit};
+
+    use super::{is_identifier, provide_rename};
+    use crate::{providers::FeatureRequest, tests::fixtures::TestStore};
+
+    fn rename(fixture: &str, new_name: &str) -> Result<Option<WorkspaceEdit>, String> {
+        let test_store = TestStore::new(fixture).unwrap();
+        let cursor = test_store.cursor().unwrap();
+        let uri = Arc::new(cursor.text_document.uri.clone());
+        let request = FeatureRequest {
+            params: RenameParams {
+                text_document_position: cursor,
+                new_name: new_name.to_string(),
+                work_done_progress_params: Default::default(),
+            },
+            store: test_store.store.clone(),
+            uri,
+        };
+
+        provide_rename(request)
+    }
+
+    const GLOBAL: &str = r#"
+//! main.sp
+int foo;
+    |
+
+void bar()
+{
+    int baz = foo;
+}
+
+void qux()
+{
+    int quux;
+}
+"#;
+
+    #[test]
+    fn rename_conflicts_with_a_local_of_a_reference() {
+        let err = rename(GLOBAL, "baz").unwrap_err();
+        assert!(err.starts_with("`baz` is already declared in "), "{}", err);
+    }
+
+    #[test]
+    fn rename_ignores_the_locals_of_other_functions() {
+        let edit = rename(GLOBAL, "quux").unwrap().unwrap();
+        let edits = edit.changes.unwrap().into_values().next().unwrap();
+        assert_eq!(edits.len(), 2);
+        assert!(edits.iter().all(|edit| edit.new_text == "quux"));
+    }
+
+    #[test]
+    fn is_identifier_rejects_invalid_names() {
+        assert!(is_identifier("g_hDatabase"));
+        assert!(is_identifier("_foo2"));
+        assert!(!is_identifier(""));
+        assert!(!is_identifier("2foo"));
+        assert!(!is_identifier("foo-bar"));
+    }
 }
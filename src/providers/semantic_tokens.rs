@@ -1,21 +1,120 @@
+use std::sync::Arc;
+
 use lsp_types::{
-    SemanticTokenModifier, SemanticTokenType, SemanticTokens, SemanticTokensLegend,
-    SemanticTokensParams,
+    Range, SemanticToken, SemanticTokenModifier, SemanticTokenType, SemanticTokens,
+    SemanticTokensDelta, SemanticTokensDeltaParams, SemanticTokensEdit,
+    SemanticTokensFullDeltaResult, SemanticTokensLegend, SemanticTokensParams,
+    SemanticTokensRangeParams, SemanticTokensRangeResult, Url,
 };
 
-use crate::spitem::SPItem;
+use crate::{spitem::SPItem, store::Store};
 
 use self::builder::SemanticTokensBuilder;
 
 use super::FeatureRequest;
 
 mod builder;
+mod cache;
+
+pub use self::cache::SemanticTokensCache;
 
 pub fn provide_semantic_tokens(
     request: FeatureRequest<SemanticTokensParams>,
 ) -> Option<SemanticTokens> {
-    let uri = request.uri;
-    let all_items = &request.store.get_all_items(false).0;
+    let revision = request.store.revision;
+    if let Some((result_id, data)) = request
+        .store
+        .semantic_tokens_cache
+        .lock()
+        .unwrap()
+        .get_current(&request.uri, revision)
+    {
+        // Nothing changed since the last response.
+        return Some(SemanticTokens {
+            result_id: Some(result_id.to_string()),
+            data: data.clone(),
+        });
+    }
+    let data = build_tokens(&request.store, &request.uri, None)?;
+    let result_id = request.store.semantic_tokens_cache.lock().unwrap().insert(
+        request.uri.clone(),
+        revision,
+        data.clone(),
+    );
+
+    Some(SemanticTokens {
+        result_id: Some(result_id),
+        data,
+    })
+}
+
+pub fn provide_semantic_tokens_delta(
+    request: FeatureRequest<SemanticTokensDeltaParams>,
+) -> Option<SemanticTokensFullDeltaResult> {
+    let revision = request.store.revision;
+    if let Some((result_id, data)) = request
+        .store
+        .semantic_tokens_cache
+        .lock()
+        .unwrap()
+        .get_current(&request.uri, revision)
+    {
+        // Nothing changed since the last response, don't build the tokens again.
+        let result_id = Some(result_id.to_string());
+        return Some(if result_id == Some(request.params.previous_result_id) {
+            SemanticTokensFullDeltaResult::TokensDelta(SemanticTokensDelta {
+                result_id,
+                edits: vec![],
+            })
+        } else {
+            SemanticTokensFullDeltaResult::Tokens(SemanticTokens {
+                result_id,
+                data: data.clone(),
+            })
+        });
+    }
+    let data = build_tokens(&request.store, &request.uri, None)?;
+    let mut cache = request.store.semantic_tokens_cache.lock().unwrap();
+    let edits = cache
+        .get(&request.uri, &request.params.previous_result_id)
+        .map(|previous_data| diff_tokens(previous_data, &data));
+    let result_id = cache.insert(request.uri.clone(), revision, data.clone());
+
+    match edits {
+        Some(edits) => Some(SemanticTokensFullDeltaResult::TokensDelta(
+            SemanticTokensDelta {
+                result_id: Some(result_id),
+                edits,
+            },
+        )),
+        None => Some(SemanticTokensFullDeltaResult::Tokens(SemanticTokens {
+            result_id: Some(result_id),
+            data,
+        })),
+    }
+}
+
+pub fn provide_semantic_tokens_range(
+    request: FeatureRequest<SemanticTokensRangeParams>,
+) -> Option<SemanticTokensRangeResult> {
+    let data = build_tokens(&request.store, &request.uri, Some(request.params.range))?;
+
+    Some(SemanticTokensRangeResult::Tokens(SemanticTokens {
+        result_id: None,
+        data,
+    }))
+}
+
+/// Build the encoded semantic tokens of a document.
+///
+/// # Arguments
+///
+/// * `store` - [Store] of the server.
+/// * `uri` - [Uri](Url) of the document.
+/// * `range` - Optional [Range] to restrict the tokens to. The local variables of the functions
+///   outside of the range are skipped.
+fn build_tokens(store: &Store, uri: &Arc<Url>, range: Option<Range>) -> Option<Vec<SemanticToken>> {
+    let all_items = &store.get_all_items(false).0;
     if all_items.is_empty() {
         return None;
     }
@@ -39,20 +138,98 @@ pub fn provide_semantic_tokens(
             SemanticTokenModifier::MODIFICATION,
         ],
     }));
+    if let Some(range) = range {
+        builder.restrict_to_range(range);
+    }
 
     for item in all_items.iter() {
         let item_lock = item.read().unwrap();
         match &*item_lock {
-            SPItem::Enum(enum_item) => builder.build_enum(enum_item, &uri),
-            SPItem::Variable(variable_item) => builder.build_global_variable(variable_item, &uri),
-            SPItem::Define(define_item) => builder.build_define(define_item, &uri),
-            SPItem::Function(function_item) => builder.build_function(function_item, &uri),
-            SPItem::Methodmap(mm_item) => builder.build_methodmap(mm_item, &uri),
-            SPItem::EnumStruct(es_item) => builder.build_enum_struct(es_item, &uri),
+            SPItem::Enum(enum_item) => builder.build_enum(enum_item, uri),
+            SPItem::Variable(variable_item) => builder.build_global_variable(variable_item, uri),
+            SPItem::Define(define_item) => builder.build_define(define_item, uri),
+            SPItem::Function(function_item) => builder.build_function(function_item, uri),
+            SPItem::Methodmap(mm_item) => builder.build_methodmap(mm_item, uri),
+            SPItem::EnumStruct(es_item) => builder.build_enum_struct(es_item, uri),
             _ => Ok(()),
         }
         .unwrap_or_default();
     }
 
-    Some(builder.build(None))
+    Some(builder.build(None).data)
+}
+
+/// Compute the edits which turn the previous tokens into the new ones. The unchanged tokens at
+/// the start and at the end are kept, everything in between is replaced by a single edit.
+///
+/// # Arguments
+///
+/// * `previous` - Tokens of the previous response.
+/// * `current` - New tokens.
+fn diff_tokens(previous: &[SemanticToken], current: &[SemanticToken]) -> Vec<SemanticTokensEdit> {
+    let prefix = previous
+        .iter()
+        .zip(current.iter())
+        .take_while(|(a, b)| a == b)
+        .count();
+    if prefix == previous.len() && prefix == current.len() {
+        return vec![];
+    }
+    let suffix = previous[prefix..]
+        .iter()
+        .rev()
+        .zip(current[prefix..].iter().rev())
+        .take_while(|(a, b)| a == b)
+        .count();
+
+    // Each token is encoded as 5 integers.
+    vec![SemanticTokensEdit {
+        start: (prefix * 5) as u32,
+        delete_count: ((previous.len() - prefix - suffix) * 5) as u32,
+        data: Some(current[prefix..current.len() - suffix].to_vec()),
+    }]
+}
+
+#[cfg(test)]
+mod tests {
+    use lsp_types::SemanticToken;
+
+    use super::diff_tokens;
+
+    fn token(delta_line: u32) -> SemanticToken {
+        SemanticToken {
+            delta_line,
+            delta_start: 0,
+            length: 1,
+            token_type: 0,
+            token_modifiers_bitset: 0,
+        }
+    }
+
+    #[test]
+    fn diff_tokens_identical() {
+        let tokens = vec![token(0), token(1)];
+        assert!(diff_tokens(&tokens, &tokens).is_empty());
+    }
+
+    #[test]
+    fn diff_tokens_replaces_middle() {
+        let previous = vec![token(0), token(1), token(2)];
+        let current = vec![token(0), token(5), token(6), token(2)];
+        let edits = diff_tokens(&previous, &current);
+        assert_eq!(edits.len(), 1);
+        assert_eq!(edits[0].start, 5);
+        assert_eq!(edits[0].delete_count, 5);
+        assert_eq!(edits[0].data, Some(vec![token(5), token(6)]));
+    }
+
+    #[test]
+    fn diff_tokens_removes_tail() {
+        let previous = vec![token(0), token(1), token(1)];
+        let current = vec![token(0), token(1)];
+        let edits = diff_tokens(&previous, &current);
+        assert_eq!(edits[0].start, 10);
+        assert_eq!(edits[0].delete_count, 5);
+        assert_eq!(edits[0].data, Some(vec![]));
+    }
 }
@@ -27,6 +27,7 @@ pub(super) struct SemanticTokensBuilder {
     token_type_str_to_int: FxHashMap<SemanticTokenType, u32>,
     token_modifier_str_to_int: FxHashMap<SemanticTokenModifier, u32>,
     has_legend: bool,
+    range: Option<Range>,
 }
 
 impl SemanticTokensBuilder {
@@ -52,6 +53,27 @@ impl SemanticTokensBuilder {
         builder
     }
 
+    /// Only keep the tokens which start on the lines of a range.
+    ///
+    /// # Arguments
+    ///
+    /// * `range` - [Range] to restrict the tokens to.
+    pub(super) fn restrict_to_range(&mut self, range: Range) {
+        self.range = Some(range);
+    }
+
+    /// Check if a range overlaps the lines the tokens are restricted to, if any.
+    ///
+    /// # Arguments
+    ///
+    /// * `range` - [Range] to check.
+    pub(super) fn overlaps_range(&self, range: &Range) -> bool {
+        self.range.map_or(true, |restricted_range| {
+            range.end.line >= restricted_range.start.line
+                && range.start.line <= restricted_range.end.line
+        })
+    }
+
     pub(super) fn push(
         &mut self,
         range: Range,
@@ -67,6 +89,9 @@ impl SemanticTokensBuilder {
         if !self.token_type_str_to_int.contains_key(&token_type) {
             return Err(anyhow!("{:?} is not in the provided legend", token_type));
         }
+        if !self.overlaps_range(&range) {
+            return Ok(());
+        }
 
         let line = range.start.line;
         let char = range.start.character;
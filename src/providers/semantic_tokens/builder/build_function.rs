@@ -38,6 +38,10 @@ impl SemanticTokensBuilder {
                 self.push(ref_.v_range, type_.clone(), Some(modifiers))?;
             }
         }
+        if !function_item.uri.eq(uri) || !self.overlaps_range(&function_item.v_full_range) {
+            // The local variables are only referenced in the body of the function.
+            return Ok(());
+        }
         function_item.children.iter().for_each(|child| {
             if let SPItem::Variable(variable_item) = &*child.read().unwrap() {
                 self.build_local_variable(variable_item, uri)
@@ -29,6 +29,10 @@ impl SemanticTokensBuilder {
                 self.push(ref_.v_range, token_type.clone(), Some(vec![]))?;
             }
         }
+        if !method_item.uri.eq(uri) || !self.overlaps_range(&method_item.v_full_range) {
+            // The local variables are only referenced in the body of the function.
+            return Ok(());
+        }
         method_item.children.iter().for_each(|child| {
             if let SPItem::Variable(variable_item) = &*child.read().unwrap() {
                 self.build_local_variable(variable_item, uri)
@@ -0,0 +1,88 @@
+use std::sync::Arc;
+
+use fxhash::FxHashMap;
+use lsp_types::{SemanticToken, Url};
+
+/// Last semantic tokens sent to the client for each document, used to answer delta requests.
+#[derive(Debug, Default)]
+pub struct SemanticTokensCache {
+    next_result_id: u64,
+    results: FxHashMap<Arc<Url>, CachedTokens>,
+}
+
+#[derive(Debug)]
+struct CachedTokens {
+    result_id: String,
+    /// [Revision](crate::store::Store::revision) of the store the tokens were built from.
+    revision: u64,
+    data: Vec<SemanticToken>,
+}
+
+impl SemanticTokensCache {
+    /// Cache the tokens of a document and return their new result id.
+    ///
+    /// # Arguments
+    ///
+    /// * `uri` - [Uri](Url) of the document.
+    /// * `revision` - Revision of the store the tokens were built from.
+    /// * `data` - Encoded tokens sent to the client.
+    pub(super) fn insert(
+        &mut self,
+        uri: Arc<Url>,
+        revision: u64,
+        data: Vec<SemanticToken>,
+    ) -> String {
+        self.next_result_id += 1;
+        let result_id = self.next_result_id.to_string();
+        self.results.insert(
+            uri,
+            CachedTokens {
+                result_id: result_id.clone(),
+                revision,
+                data,
+            },
+        );
+
+        result_id
+    }
+
+    /// Return the cached tokens of a document if they match the given result id.
+    ///
+    /// # Arguments
+    ///
+    /// * `uri` - [Uri](Url) of the document.
+    /// * `result_id` - Result id of the previous response.
+    pub(super) fn get(&self, uri: &Url, result_id: &str) -> Option<&Vec<SemanticToken>> {
+        self.results
+            .get(uri)
+            .filter(|cached| cached.result_id == result_id)
+            .map(|cached| &cached.data)
+    }
+
+    /// Return the result id and the cached tokens of a document if they were built from the
+    /// given revision of the store, in which case they are still up to date.
+    ///
+    /// # Arguments
+    ///
+    /// * `uri` - [Uri](Url) of the document.
+    /// * `revision` - Current revision of the store.
+    pub(super) fn get_current(
+        &self,
+        uri: &Url,
+        revision: u64,
+    ) -> Option<(&str, &Vec<SemanticToken>)> {
+        self.results
+            .get(uri)
+            .filter(|cached| cached.revision == revision)
+            .map(|cached| (cached.result_id.as_str(), &cached.data))
+    }
+
+    /// Forget the tokens of a document.
+    ///
+    /// # Arguments
+    ///
+    /// * `uri` - [Uri](Url) of the document.
+    pub fn remove(&mut self, uri: &Url) {
+        self.results.remove(uri);
+    }
+}
@@ -0,0 +1,136 @@
+use lsp_types::request::{GotoTypeDefinitionParams, GotoTypeDefinitionResponse};
+
+use crate::{semantic_analyzer::type_resolver::type_name, spitem::SPItem};
+
+use super::FeatureRequest;
+
+/// Build the type definitions of the variable or property under the cursor, which are the
+/// methodmaps, enum structs and enums named by its type.
+///
+/// # Arguments
+///
+/// * `request` - Type definition request object [FeatureRequest<GotoTypeDefinitionParams>].
+pub fn provide_type_definition(
+    request: FeatureRequest<GotoTypeDefinitionParams>,
+) -> Option<GotoTypeDefinitionResponse> {
+    let items = &request.store.get_items_from_position(
+        request.params.text_document_position_params.position,
+        request
+            .params
+            .text_document_position_params
+            .text_document
+            .uri
+            .clone(),
+    );
+    let type_names: Vec<String> = items
+        .iter()
+        .filter_map(|item| match &*item.read().unwrap() {
+            SPItem::Variable(variable_item) => Some(type_name(&variable_item.type_)),
+            SPItem::Property(property_item) => Some(type_name(&property_item.type_)),
+            _ => None,
+        })
+        .collect();
+    if type_names.is_empty() {
+        return None;
+    }
+    let mut definitions = vec![];
+    for item in request.store.get_all_items(false).0.iter() {
+        let item = item.read().unwrap();
+        if matches!(
+            &*item,
+            SPItem::Methodmap(_) | SPItem::EnumStruct(_) | SPItem::Enum(_)
+        ) && type_names.contains(&item.name())
+        {
+            definitions.extend(item.to_definition(&request.params));
+        }
+    }
+
+    Some(GotoTypeDefinitionResponse::Link(definitions))
+}
+
+#[cfg(test)]
+mod tests {
+    use std::sync::Arc;
+
+    use lsp_types::{request::GotoTypeDefinitionResponse, GotoDefinitionParams, Location};
+
+    use super::provide_type_definition;
+    use crate::{providers::FeatureRequest, tests::fixtures::TestStore};
+
+    fn type_definitions(test_store: &TestStore) -> Option<Vec<Location>> {
+        let cursor = test_store.cursor().unwrap();
+        let uri = Arc::new(cursor.text_document.uri.clone());
+        let request = FeatureRequest {
+            params: GotoDefinitionParams {
+                text_document_position_params: cursor,
+                work_done_progress_params: Default::default(),
+                partial_result_params: Default::default(),
+            },
+            store: test_store.store.clone(),
+            uri,
+        };
+
+        match provide_type_definition(request)? {
+            GotoTypeDefinitionResponse::Link(links) => Some(
+                links
+                    .into_iter()
+                    .map(|link| Location::new(link.target_uri, link.target_selection_range))
+                    .collect(),
+            ),
+            _ => None,
+        }
+    }
+
+    #[test]
+    fn methodmap_of_a_variable() {
+        let test_store = TestStore::new(
+            r#"
+//! main.sp
+methodmap Foo < Handle {}
+          ^^^
+
+void foo()
+{
+    Foo foo;
+        |
+}
+"#,
+        )
+        .unwrap();
+        assert_eq!(type_definitions(&test_store), Some(test_store.locations()));
+    }
+
+    #[test]
+    fn enum_struct_of_an_array() {
+        let test_store = TestStore::new(
+            r#"
+//! main.sp
+enum struct Foo
+            ^^^
+{
+    int bar;
+}
+
+Foo foos[2];
+     |
+"#,
+        )
+        .unwrap();
+        assert_eq!(type_definitions(&test_store), Some(test_store.locations()));
+    }
+
+    #[test]
+    fn function_has_no_type_definition() {
+        let test_store = TestStore::new(
+            r#"
+//! main.sp
+void foo()
+      |
+{
+}
+"#,
+        )
+        .unwrap();
+        assert_eq!(type_definitions(&test_store), None);
+    }
+}
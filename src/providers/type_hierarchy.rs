@@ -0,0 +1,234 @@
+use std::sync::{Arc, RwLock};
+
+use lsp_types::{
+    TypeHierarchyItem, TypeHierarchyPrepareParams, TypeHierarchySubtypesParams,
+    TypeHierarchySupertypesParams,
+};
+
+use crate::spitem::SPItem;
+
+use super::FeatureRequest;
+
+/// Build the type hierarchy item of the methodmap under the cursor.
+///
+/// # Arguments
+///
+/// * `request` - Type hierarchy prepare request object [FeatureRequest<TypeHierarchyPrepareParams>].
+pub fn prepare(
+    request: FeatureRequest<TypeHierarchyPrepareParams>,
+) -> Option<Vec<TypeHierarchyItem>> {
+    let items = &request.store.get_items_from_position(
+        request.params.text_document_position_params.position,
+        request
+            .params
+            .text_document_position_params
+            .text_document
+            .uri
+            .clone(),
+    );
+    if items.is_empty() {
+        return None;
+    }
+
+    let item = items[0].read().unwrap();
+    if let SPItem::Methodmap(methodmap_item) = &*item {
+        Some(vec![methodmap_item.to_type_hierarchy()])
+    } else {
+        None
+    }
+}
+
+/// Build the type hierarchy item of the methodmap the requested methodmap inherits from.
+///
+/// # Arguments
+///
+/// * `request` - Supertypes request object [FeatureRequest<TypeHierarchySupertypesParams>].
+pub fn supertypes(
+    request: FeatureRequest<TypeHierarchySupertypesParams>,
+) -> Option<Vec<TypeHierarchyItem>> {
+    let methodmaps = methodmaps(&request.store.get_all_items(false).0);
+    let parent_name = methodmaps
+        .iter()
+        .find_map(|item| match &*item.read().unwrap() {
+            SPItem::Methodmap(methodmap_item)
+                if methodmap_item.name == request.params.item.name =>
+            {
+                methodmap_item.parent_name()
+            }
+            _ => None,
+        })?;
+
+    Some(
+        methodmaps
+            .iter()
+            .filter_map(|item| match &*item.read().unwrap() {
+                SPItem::Methodmap(methodmap_item) if methodmap_item.name == parent_name => {
+                    Some(methodmap_item.to_type_hierarchy())
+                }
+                _ => None,
+            })
+            .collect(),
+    )
+}
+
+/// Build the type hierarchy items of the methodmaps which directly inherit from the requested
+/// methodmap.
+///
+/// # Arguments
+///
+/// * `request` - Subtypes request object [FeatureRequest<TypeHierarchySubtypesParams>].
+pub fn subtypes(
+    request: FeatureRequest<TypeHierarchySubtypesParams>,
+) -> Option<Vec<TypeHierarchyItem>> {
+    Some(
+        methodmaps(&request.store.get_all_items(false).0)
+            .iter()
+            .filter_map(|item| match &*item.read().unwrap() {
+                SPItem::Methodmap(methodmap_item)
+                    if methodmap_item.parent_name().as_ref() == Some(&request.params.item.name) =>
+                {
+                    Some(methodmap_item.to_type_hierarchy())
+                }
+                _ => None,
+            })
+            .collect(),
+    )
+}
+
+/// Return the methodmaps of a list of [items](SPItem).
+///
+/// # Arguments
+///
+/// * `all_items` - First level [items](SPItem) to filter.
+fn methodmaps(all_items: &[Arc<RwLock<SPItem>>]) -> Vec<Arc<RwLock<SPItem>>> {
+    all_items
+        .iter()
+        .filter(|item| matches!(&*item.read().unwrap(), SPItem::Methodmap(_)))
+        .cloned()
+        .collect()
+}
+
+#[cfg(test)]
+mod tests {
+    use std::sync::Arc;
+
+    use lsp_types::{
+        TypeHierarchyItem, TypeHierarchyPrepareParams, TypeHierarchySubtypesParams,
+        TypeHierarchySupertypesParams,
+    };
+
+    use super::{prepare, subtypes, supertypes};
+    use crate::{providers::FeatureRequest, tests::fixtures::TestStore};
+
+    const FIXTURE: &str = r#"
+//! main.sp
+methodmap Animal < Handle {}
+
+methodmap Dog < Animal {}
+          |
+
+methodmap Puppy < Dog {}
+
+methodmap Cat < Animal {}
+"#;
+
+    fn request<P>(test_store: &TestStore, params: P) -> FeatureRequest<P> {
+        FeatureRequest {
+            params,
+            store: test_store.store.clone(),
+            uri: test_store.uri("main.sp"),
+        }
+    }
+
+    fn prepare_dog(test_store: &TestStore) -> TypeHierarchyItem {
+        let items = prepare(request(
+            test_store,
+            TypeHierarchyPrepareParams {
+                text_document_position_params: test_store.cursor().unwrap(),
+                work_done_progress_params: Default::default(),
+            },
+        ))
+        .unwrap();
+        assert_eq!(items.len(), 1);
+
+        items[0].clone()
+    }
+
+    fn names(items: Option<Vec<TypeHierarchyItem>>) -> Vec<String> {
+        let mut names: Vec<String> = items.unwrap().into_iter().map(|item| item.name).collect();
+        names.sort();
+
+        names
+    }
+
+    #[test]
+    fn prepare_methodmap_under_the_cursor() {
+        let test_store = TestStore::new(FIXTURE).unwrap();
+        let item = prepare_dog(&test_store);
+        assert_eq!(item.name, "Dog");
+        assert_eq!(Arc::new(item.uri), test_store.uri("main.sp"));
+    }
+
+    #[test]
+    fn supertypes_of_a_methodmap() {
+        let test_store = TestStore::new(FIXTURE).unwrap();
+        let item = prepare_dog(&test_store);
+        assert_eq!(
+            names(supertypes(request(
+                &test_store,
+                TypeHierarchySupertypesParams {
+                    item,
+                    work_done_progress_params: Default::default(),
+                    partial_result_params: Default::default(),
+                },
+            ))),
+            vec!["Animal"]
+        );
+    }
+
+    #[test]
+    fn subtypes_of_a_methodmap() {
+        let test_store = TestStore::new(FIXTURE).unwrap();
+        let item = prepare_dog(&test_store);
+        assert_eq!(
+            names(subtypes(request(
+                &test_store,
+                TypeHierarchySubtypesParams {
+                    item,
+                    work_done_progress_params: Default::default(),
+                    partial_result_params: Default::default(),
+                },
+            ))),
+            vec!["Puppy"]
+        );
+    }
+
+    #[test]
+    fn supertypes_of_a_root_methodmap() {
+        let test_store = TestStore::new(FIXTURE).unwrap();
+        let mut item = prepare_dog(&test_store);
+        item.name = "Animal".to_string();
+        assert_eq!(
+            names(supertypes(request(
+                &test_store,
+                TypeHierarchySupertypesParams {
+                    item: item.clone(),
+                    work_done_progress_params: Default::default(),
+                    partial_result_params: Default::default(),
+                },
+            ))),
+            Vec::<String>::new()
+        );
+        assert_eq!(
+            names(subtypes(request(
+                &test_store,
+                TypeHierarchySubtypesParams {
+                    item,
+                    work_done_progress_params: Default::default(),
+                    partial_result_params: Default::default(),
+                },
+            ))),
+            vec!["Cat", "Dog"]
+        );
+    }
+}
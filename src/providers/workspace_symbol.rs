@@ -0,0 +1,147 @@
+use std::sync::{Arc, RwLock};
+
+use lsp_types::{SymbolInformation, WorkspaceSymbolParams, WorkspaceSymbolResponse};
+
+use crate::{spitem::SPItem, utils::uri_to_file_name};
+
+use super::WorkspaceRequest;
+
+/// Maximum number of symbols returned for a query.
+const MAX_RESULTS: usize = 512;
+
+pub fn provide_workspace_symbol(
+    request: WorkspaceRequest<WorkspaceSymbolParams>,
+) -> Option<WorkspaceSymbolResponse> {
+    let query = request.params.query;
+    let mut symbols: Vec<(i32, SymbolInformation)> = vec![];
+    for document in request.store.documents.values() {
+        for item in document.sp_items.iter() {
+            push_symbols(item, &query, &mut symbols);
+        }
+    }
+    symbols.sort_by(|a, b| b.0.cmp(&a.0).then_with(|| a.1.name.cmp(&b.1.name)));
+    symbols.truncate(MAX_RESULTS);
+
+    Some(WorkspaceSymbolResponse::Flat(
+        symbols.into_iter().map(|(_, symbol)| symbol).collect(),
+    ))
+}
+
+/// Push the [SymbolInformation] of an item and of its children if they match the query.
+///
+/// # Arguments
+///
+/// * `item` - Item to convert.
+/// * `query` - Query of the request.
+/// * `symbols` - Vector of scored symbols to push into.
+fn push_symbols(
+    item: &Arc<RwLock<SPItem>>,
+    query: &str,
+    symbols: &mut Vec<(i32, SymbolInformation)>,
+) {
+    let item = item.read().unwrap();
+    match &*item {
+        SPItem::Function(_)
+        | SPItem::Define(_)
+        | SPItem::Enum(_)
+        | SPItem::Typedef(_)
+        | SPItem::Typeset(_) => (),
+        SPItem::Methodmap(_) | SPItem::EnumStruct(_) => {
+            if let Some(children) = item.children() {
+                for child in children.iter() {
+                    push_symbols(child, query, symbols);
+                }
+            }
+        }
+        _ => return,
+    }
+    let name = item.name();
+    let Some(score) = fuzzy_score(query, &name) else {
+        return;
+    };
+    let Some(symbol) = item.to_document_symbol() else {
+        return;
+    };
+    let uri = item.uri();
+    #[allow(deprecated)]
+    symbols.push((
+        score,
+        SymbolInformation {
+            name,
+            kind: symbol.kind,
+            tags: symbol.tags,
+            deprecated: None,
+            location: lsp_types::Location::new(uri.as_ref().clone(), item.v_range()),
+            container_name: match item.parent() {
+                Some(parent) => uri_to_file_name(&uri)
+                    .map(|file_name| format!("{} ({})", parent.read().unwrap().name(), file_name)),
+                None => uri_to_file_name(&uri),
+            },
+        },
+    ));
+}
+
+/// Score how well a candidate matches a query. Return [None] if the characters of the query
+/// do not appear, in order, in the candidate. The comparison is case insensitive.
+///
+/// Consecutive characters and characters at the start of a word are favored.
+///
+/// # Arguments
+///
+/// * `query` - Query to match.
+/// * `candidate` - Candidate to score.
+fn fuzzy_score(query: &str, candidate: &str) -> Option<i32> {
+    if query.is_empty() {
+        return Some(0);
+    }
+    let candidate: Vec<char> = candidate.chars().collect();
+    let mut score = 0;
+    let mut index = 0;
+    let mut previous_match: Option<usize> = None;
+    for query_char in query.chars().map(|c| c.to_ascii_lowercase()) {
+        loop {
+            let candidate_char = *candidate.get(index)?;
+            if candidate_char.to_ascii_lowercase() == query_char {
+                break;
+            }
+            index += 1;
+        }
+        score += 1;
+        if previous_match.map_or(index == 0, |previous| previous + 1 == index) {
+            score += 5;
+        }
+        if index == 0
+            || candidate[index - 1] == '_'
+            || (candidate[index].is_uppercase() && candidate[index - 1].is_lowercase())
+        {
+            score += 3;
+        }
+        previous_match = Some(index);
+        index += 1;
+    }
+    if candidate.len() == query.chars().count() {
+        // Exact match.
+        score += 10;
+    }
+
+    Some(score - candidate.len() as i32 / 4)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::fuzzy_score;
+
+    #[test]
+    fn fuzzy_score_matches_subsequences() {
+        assert!(fuzzy_score("gcn", "GetClientName").is_some());
+        assert!(fuzzy_score("getclient", "GetClientName").is_some());
+        assert!(fuzzy_score("nmae", "GetClientName").is_none());
+    }
+
+    #[test]
+    fn fuzzy_score_favors_word_starts() {
+        assert!(
+            fuzzy_score("client", "Client").unwrap() > fuzzy_score("client", "IsClient").unwrap()
+        );
+    }
+}
@@ -8,7 +8,7 @@ use crate::{
     spitem::SPItem,
 };
 
-use super::{purge_references, scope::Scope};
+use super::{purge_references_in_range, scope::Scope};
 
 #[derive(Debug, Default)]
 pub struct Analyzer {
@@ -26,13 +26,17 @@ pub struct Analyzer {
 }
 
 impl Analyzer {
-    pub fn new(all_items: Vec<Arc<RwLock<SPItem>>>, document: &Document) -> Self {
+    pub fn new(
+        all_items: Vec<Arc<RwLock<SPItem>>>,
+        document: &Document,
+        range: Option<Range>,
+    ) -> Self {
         let mut tokens_map = FxHashMap::default();
         let mut funcs_in_file = vec![];
         let mut mm_es_in_file = vec![];
 
         for item in all_items.iter() {
-            purge_references(item, &document.uri);
+            purge_references_in_range(item, &document.uri, range);
             match &*item.read().unwrap() {
                 // Match variables
                 SPItem::Variable(variable_item) => {
@@ -47,7 +51,7 @@ impl Analyzer {
                     tokens_map.insert(function_item.key(), item.clone());
                     // All variables of the function.
                     for child in &function_item.children {
-                        purge_references(child, &document.uri);
+                        purge_references_in_range(child, &document.uri, range);
                         tokens_map.insert(child.read().unwrap().key(), child.clone());
                     }
                 }
@@ -58,7 +62,7 @@ impl Analyzer {
                     tokens_map.insert(methodmap_item.key(), item.clone());
                     // All properties and methods of the enum struct.
                     for child in &methodmap_item.children {
-                        purge_references(child, &document.uri);
+                        purge_references_in_range(child, &document.uri, range);
                         tokens_map.insert(child.read().unwrap().key(), child.clone());
                         if let SPItem::Function(method_item) = &*child.read().unwrap() {
                             if method_item.uri.eq(&document.uri) {
@@ -66,7 +70,7 @@ impl Analyzer {
                             }
                             // All variables of the method.
                             for sub_child in &method_item.children {
-                                purge_references(sub_child, &document.uri);
+                                purge_references_in_range(sub_child, &document.uri, range);
                                 tokens_map
                                     .insert(sub_child.read().unwrap().key(), sub_child.clone());
                             }
@@ -80,7 +84,7 @@ impl Analyzer {
                     tokens_map.insert(enum_struct_item.key(), item.clone());
                     // All fields and methods of the enum struct.
                     for child in &enum_struct_item.children {
-                        purge_references(child, &document.uri);
+                        purge_references_in_range(child, &document.uri, range);
                         tokens_map.insert(child.read().unwrap().key(), child.clone());
                         if let SPItem::Function(method_item) = &*child.read().unwrap() {
                             if method_item.uri.eq(&document.uri) {
@@ -88,7 +92,7 @@ impl Analyzer {
                             }
                             // All variables of the method.
                             for sub_child in &method_item.children {
-                                purge_references(sub_child, &document.uri);
+                                purge_references_in_range(sub_child, &document.uri, range);
                                 tokens_map
                                     .insert(sub_child.read().unwrap().key(), sub_child.clone());
                             }
@@ -99,7 +103,7 @@ impl Analyzer {
                     tokens_map.insert(enum_item.key(), item.clone());
                     // All enum members of the enum.
                     for child in &enum_item.children {
-                        purge_references(child, &document.uri);
+                        purge_references_in_range(child, &document.uri, range);
                         tokens_map.insert(child.read().unwrap().key(), child.clone());
                     }
                 }
@@ -120,7 +124,7 @@ impl Analyzer {
                     tokens_map.insert(typeset_item.key(), item.clone());
                     // All typedef members of the typeset.
                     for child in &typeset_item.children {
-                        purge_references(child, &document.uri);
+                        purge_references_in_range(child, &document.uri, range);
                         tokens_map.insert(child.read().unwrap().key(), child.clone());
                     }
                 }
@@ -132,6 +136,9 @@ impl Analyzer {
         Self {
             tokens_map,
             all_items,
+            // When only resolving a range, skip the scopes which end before it.
+            func_idx: first_scope_idx(&funcs_in_file, range),
+            mm_es_idx: first_scope_idx(&mm_es_in_file, range),
             funcs_in_file,
             mm_es_in_file,
             lines: document
@@ -170,3 +177,19 @@ impl Analyzer {
         }
     }
 }
+
+/// Return the index of the first scope item which does not end before a range.
+///
+/// # Arguments
+///
+/// * `items` - Scope items of the document, in order.
+/// * `range` - Optional [Range] the resolution starts at.
+fn first_scope_idx(items: &[Arc<RwLock<SPItem>>], range: Option<Range>) -> usize {
+    match range {
+        Some(range) => items
+            .iter()
+            .position(|item| item.read().unwrap().full_range().end >= range.start)
+            .unwrap_or(items.len()),
+        None => 0,
+    }
+}
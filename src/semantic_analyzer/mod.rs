@@ -3,37 +3,85 @@ use std::{
     time::Instant,
 };
 
-use fxhash::{FxHashMap, FxHashSet};
-use lsp_types::Url;
+use fxhash::FxHashMap;
+use lsp_types::{Range, Url};
 
 pub mod analyzer;
 pub mod inherit;
 mod resolvers;
 pub mod scope;
+pub mod type_resolver;
 
-use crate::{document::SPToken, spitem::SPItem, store::Store};
+use crate::{
+    document::SPToken, linter::checker::Checker, spitem::SPItem, store::Store,
+    utils::range_contains_range,
+};
 
-use self::analyzer::Analyzer;
+use self::{analyzer::Analyzer, type_resolver::TypeResolver};
 
 impl Store {
     pub(crate) fn find_references(&mut self, uri: &Url) -> Option<()> {
+        self.resolve_references(uri, None)
+    }
+
+    /// Resolve the references of the tokens of a document which are in a range. The references
+    /// of the other tokens are left untouched.
+    ///
+    /// # Arguments
+    ///
+    /// * `uri` - [Uri](Url) of the document.
+    /// * `range` - [Range] of the tokens to resolve.
+    pub(crate) fn find_references_in_range(&mut self, uri: &Url, range: Range) -> Option<()> {
+        self.resolve_references(uri, Some(range))
+    }
+
+    fn resolve_references(&mut self, uri: &Url, range: Option<Range>) -> Option<()> {
         log::trace!("Resolving references for document {:?}", uri);
         if !self.documents.contains_key(uri) {
             log::trace!("Skipped resolving references for document {:?}", uri);
             return None;
         }
+        self.revision += 1;
         let now = Instant::now();
-        let (all_items, include_duration) = self.get_all_items(false);
+        let (all_items, include_duration) =
+            self.get_all_items_of_main(self.main_uri_of(uri), false);
         self.get_all_items_time.push(now.elapsed());
         self.get_includes_time.push(include_duration);
+        let in_includes_directories = self.is_in_includes_directories(uri);
+        let check =
+            !self.environment.options.disable_checker_diagnostics && !in_includes_directories;
         let document = self.documents.get_mut(uri)?;
-        let mut unresolved_tokens = FxHashSet::default();
-        let mut analyzer = Analyzer::new(all_items, document);
+        let mut unresolved_tokens = match range {
+            // The tokens outside of the range are not resolved again, keep their state.
+            Some(range) => {
+                // The unresolved tokens are stored with user visible ranges.
+                let v_range = document.build_v_range(&range);
+                let mut unresolved_tokens = document.unresolved_tokens.clone();
+                for ranges in unresolved_tokens.values_mut() {
+                    ranges.retain(|token_range| !range_contains_range(&v_range, token_range));
+                }
+                unresolved_tokens.retain(|_, ranges| !ranges.is_empty());
+                unresolved_tokens
+            }
+            None => FxHashMap::default(),
+        };
+        let mut analyzer = Analyzer::new(all_items, document, range);
         document.tokens.sort_by_key(|sp_token| match sp_token {
             SPToken::Symbol(token) => token.range.start.line,
             SPToken::Method((_, field)) => field.range.start.line,
         });
+        // Resolve the members of the chained expressions from the tree when it is available.
+        let resolver = TypeResolver::new(&analyzer.all_items, document);
         for token in document.tokens.iter() {
+            if let Some(range) = range {
+                let token_range = match token {
+                    SPToken::Symbol(token) => token.range,
+                    SPToken::Method((_, field)) => field.range,
+                };
+                if !range_contains_range(&range, &token_range) {
+                    continue;
+                }
+            }
             match token {
                 SPToken::Symbol(token) => {
                     analyzer.update_scope(token.range);
@@ -47,26 +95,50 @@ impl Store {
                         continue;
                     }
                     // Token was not resolved
-                    unresolved_tokens.insert(token.text.clone());
+                    unresolved_tokens
+                        .entry(token.text.clone())
+                        .or_default()
+                        .push(document.build_v_range(&token.range));
                 }
                 SPToken::Method((parent, field)) => {
                     analyzer.update_scope(parent.range);
                     analyzer.update_line_context(parent);
+                    let root_node = document.tree.as_ref().map(|tree| tree.root_node());
+                    let member = root_node
+                        .and_then(|root_node| resolver.member_item_at(root_node, &field.range));
+                    // Only report the fields of the methodmaps whose members are all known.
                     if analyzer
-                        .resolve_method_item(parent, field, document)
+                        .resolve_method_item(parent, field, member, document)
                         .is_none()
+                        && root_node.map_or(false, |root_node| {
+                            resolver.has_known_methodmap_target_at(root_node, &field.range)
+                        })
                     {
                         // Token was not resolved
-                        unresolved_tokens.insert(field.text.clone());
+                        unresolved_tokens
+                            .entry(field.text.clone())
+                            .or_default()
+                            .push(document.build_v_range(&field.range));
                     }
                     analyzer.token_idx += 1;
                 }
             }
         }
+        // The whole document is checked, as the offsets of all the lines are known.
+        let checker_diagnostics = if check {
+            Checker::new(&resolver, document).check()
+        } else {
+            vec![]
+        };
         resolve_methodmap_inherits(analyzer.all_items);
         let document = self.documents.get_mut(uri).unwrap();
         document.unresolved_tokens = unresolved_tokens;
-        document.offsets.clear();
+        document.diagnostics.checker_diagnostics = checker_diagnostics;
+        if in_includes_directories {
+            // The other documents keep their offsets to build the user visible ranges of their
+            // inlay hints, and of their tokens when only part of them is resolved again.
+            document.offsets.clear();
+        }
         log::trace!("Done resolving references for document {:?}", uri);
 
         Some(())
@@ -74,6 +146,18 @@ impl Store {
 }
 
 pub fn purge_references(item: &Arc<RwLock<SPItem>>, uri: &Arc<Url>) {
+    purge_references_in_range(item, uri, None)
+}
+
+/// Remove the references of an item which come from a document, and optionally from a range
+/// of that document only.
+///
+/// # Arguments
+///
+/// * `item` - [Item](SPItem) to purge.
+/// * `uri` - [Uri](Url) of the document.
+/// * `range` - Optional [Range] of the references to remove.
+pub fn purge_references_in_range(item: &Arc<RwLock<SPItem>>, uri: &Arc<Url>, range: Option<Range>) {
     let mut new_references = vec![];
     let mut item_lock = item.write().unwrap();
     let old_references = item_lock.references();
@@ -82,7 +166,9 @@ pub fn purge_references(item: &Arc<RwLock<SPItem>>, uri: &Arc<Url>) {
     }
     let old_references = old_references.unwrap();
     for reference in old_references {
-        if reference.uri.ne(uri) {
+        let purged = reference.uri.eq(uri)
+            && range.map_or(true, |range| range_contains_range(&range, &reference.range));
+        if !purged {
             new_references.push(reference.clone());
         }
     }
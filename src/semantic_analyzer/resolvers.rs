@@ -116,12 +116,50 @@ impl Analyzer {
         anyhow::bail!("Token not found.");
     }
 
+    /// Try to solve for a method, property or field token, i.e which depends on the type of the
+    /// expression before it. Returns [None] if it did not resolve.
+    ///
+    /// # Arguments
+    ///
+    /// * `parent` - [Token] of the first symbol of the expression.
+    /// * `field` - [Token] of the field to analyze.
+    /// * `member` - Item of the field, if it was resolved by the
+    /// [TypeResolver](super::type_resolver::TypeResolver).
+    /// * `document` - [Document](super::document::Document) to analyze.
     pub(super) fn resolve_method_item(
         &mut self,
         parent: &Arc<Token>,
         field: &Arc<Token>,
+        member: Option<Arc<RwLock<SPItem>>>,
         document: &Document,
     ) -> Option<()> {
+        let item = match member {
+            Some(member) => member,
+            None => self.guess_method_item(parent, field)?,
+        };
+        let reference = Location {
+            uri: document.uri.clone(),
+            range: field.range,
+            v_range: document.build_v_range(&field.range),
+        };
+        item.write().unwrap().push_reference(reference);
+        self.previous_items.insert(field.text.clone(), item);
+
+        // TODO: Handle positional arguments
+        Some(())
+    }
+
+    /// Guess the item of a field from the type of the first symbol of the expression before it.
+    ///
+    /// # Arguments
+    ///
+    /// * `parent` - [Token] of the first symbol of the expression.
+    /// * `field` - [Token] of the field to analyze.
+    fn guess_method_item(
+        &self,
+        parent: &Arc<Token>,
+        field: &Arc<Token>,
+    ) -> Option<Arc<RwLock<SPItem>>> {
         if self.previous_items.is_empty() {
             return None;
         }
@@ -156,17 +194,6 @@ impl Analyzer {
             }
         }
 
-        item.as_ref()?;
-        let item = item.unwrap();
-        let reference = Location {
-            uri: document.uri.clone(),
-            range: field.range,
-            v_range: document.build_v_range(&field.range),
-        };
-        item.write().unwrap().push_reference(reference);
-        self.previous_items.insert(field.text.clone(), item);
-
-        // TODO: Handle positional arguments
-        Some(())
+        item
     }
 }
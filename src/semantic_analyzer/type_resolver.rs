@@ -0,0 +1,492 @@
+use std::sync::{Arc, RwLock};
+
+use fxhash::FxHashMap;
+use lsp_types::Range;
+use tree_sitter::{Node, Point};
+
+use crate::{
+    document::Document,
+    spitem::SPItem,
+    utils::{range_contains_range, ts_range_to_lsp_range},
+};
+
+/// Maximum depth of the methodmap inheritance chains, to stop on cyclic inheritances.
+const MAX_INHERITANCE_DEPTH: usize = 32;
+
+/// Kinds of the nodes which are expressions whose type can be resolved.
+const EXPRESSION_KINDS: &[&str] = &[
+    "symbol",
+    "this",
+    "field_access",
+    "scope_access",
+    "array_scope_access",
+    "call_expression",
+    "new_expression",
+    "view_as",
+    "old_type_cast",
+    "array_indexed_access",
+    "parenthesized_expression",
+];
+
+/// Type of an expression.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub(crate) enum ExpressionType {
+    /// Value tagged with a type, e.g `list` in `ArrayList list;`.
+    Value(String),
+
+    /// Methodmap or enum struct used to access its static members, e.g `ArrayList` in
+    /// `ArrayList.Foo()`.
+    Type(String),
+}
+
+impl ExpressionType {
+    /// Return the name of the type.
+    pub(crate) fn name(&self) -> &str {
+        match self {
+            ExpressionType::Value(name) | ExpressionType::Type(name) => name,
+        }
+    }
+}
+
+/// Resolver of the types of the expressions of a document, from the tree-sitter tree of its
+/// preprocessed text and the items in its scope.
+pub(crate) struct TypeResolver<'a> {
+    /// Preprocessed text of the document.
+    source: &'a str,
+
+    /// First level items and enum members, by name.
+    items: FxHashMap<String, Arc<RwLock<SPItem>>>,
+
+    /// Functions and methods declared in the document.
+    functions: Vec<Arc<RwLock<SPItem>>>,
+
+    /// Methodmaps and enum structs declared in the document.
+    types: Vec<Arc<RwLock<SPItem>>>,
+}
+
+impl<'a> TypeResolver<'a> {
+    /// Create a resolver for a document.
+    ///
+    /// # Arguments
+    ///
+    /// * `all_items` - First level [items](SPItem) in the scope of the document.
+    /// * `document` - [Document] whose expressions to resolve.
+    pub(crate) fn new(all_items: &[Arc<RwLock<SPItem>>], document: &'a Document) -> Self {
+        let mut items = FxHashMap::default();
+        let mut functions = vec![];
+        let mut types = vec![];
+        for item in all_items.iter() {
+            let item_lock = item.read().unwrap();
+            match &*item_lock {
+                SPItem::Methodmap(_) | SPItem::EnumStruct(_) => {
+                    // Types take precedence over the other items with the same name.
+                    items.insert(item_lock.name(), item.clone());
+                    if *item_lock.uri() == *document.uri {
+                        types.push(item.clone());
+                        functions.extend(
+                            item_lock
+                                .children()
+                                .into_iter()
+                                .flatten()
+                                .filter(|child| {
+                                    matches!(&*child.read().unwrap(), SPItem::Function(_))
+                                })
+                                .cloned(),
+                        );
+                    }
+                }
+                SPItem::Enum(enum_item) => {
+                    for child in enum_item.children.iter() {
+                        items
+                            .entry(child.read().unwrap().name())
+                            .or_insert_with(|| child.clone());
+                    }
+                }
+                SPItem::Include(_) => (),
+                _ => {
+                    if matches!(&*item_lock, SPItem::Function(_))
+                        && *item_lock.uri() == *document.uri
+                    {
+                        functions.push(item.clone());
+                    }
+                    items
+                        .entry(item_lock.name())
+                        .or_insert_with(|| item.clone());
+                }
+            }
+        }
+
+        Self {
+            source: &document.preprocessed_text,
+            items,
+            functions,
+            types,
+        }
+    }
+
+    /// Return the type of an expression, or [None] if it could not be resolved.
+    ///
+    /// # Arguments
+    ///
+    /// * `node` - [Node] of the expression.
+    pub(crate) fn expression_type(&self, node: Node) -> Option<ExpressionType> {
+        match node.kind() {
+            "symbol" => item_type(&self.symbol_item(node)?),
+            "this" => self
+                .enclosing_type(node)
+                .map(|type_item| ExpressionType::Value(type_item.read().unwrap().name())),
+            "field_access" | "scope_access" | "array_scope_access" => {
+                item_type(&self.member_item(node)?)
+            }
+            "call_expression" => {
+                let function_node = node.child_by_field_name("function")?;
+                let function = match function_node.kind() {
+                    "symbol" => self.symbol_item(function_node)?,
+                    _ => self.member_item(function_node)?,
+                };
+                let function = function.read().unwrap();
+                match &*function {
+                    SPItem::Function(function_item) => value_type(&function_item.type_),
+                    // Old style constructor call, e.g `ArrayList()`.
+                    SPItem::Methodmap(methodmap_item) => {
+                        Some(ExpressionType::Value(methodmap_item.name.clone()))
+                    }
+                    _ => None,
+                }
+            }
+            "new_expression" => {
+                let class_node = node
+                    .child_by_field_name("class")
+                    .or_else(|| node.named_child(0))?;
+                value_type(self.text(class_node)?)
+            }
+            "view_as" | "old_type_cast" => {
+                let type_node = node
+                    .child_by_field_name("type")
+                    .or_else(|| node.named_child(0))?;
+                value_type(self.text(type_node)?)
+            }
+            "array_indexed_access" => {
+                // The elements of an array have the tag of the array.
+                let array_node = node
+                    .child_by_field_name("array")
+                    .or_else(|| node.named_child(0))?;
+                match self.expression_type(array_node)? {
+                    ExpressionType::Value(name) => Some(ExpressionType::Value(name)),
+                    ExpressionType::Type(_) => None,
+                }
+            }
+            "parenthesized_expression" => self.expression_type(node.named_child(0)?),
+            "assignment_expression" => self.expression_type(
+                node.child_by_field_name("left")
+                    .or_else(|| node.named_child(0))?,
+            ),
+            "ternary_expression" => self.expression_type(
+                node.child_by_field_name("consequence")
+                    .or_else(|| node.named_child(1))?,
+            ),
+            "unary_expression" => {
+                if self.text(node)?.starts_with('!') {
+                    return value_type("bool");
+                }
+                self.expression_type(node.named_child(node.named_child_count().checked_sub(1)?)?)
+            }
+            "update_expression" => self.expression_type(node.named_child(0)?),
+            "binary_expression" => {
+                let left = node
+                    .child_by_field_name("left")
+                    .or_else(|| node.named_child(0))?;
+                let right = node
+                    .child_by_field_name("right")
+                    .or_else(|| node.named_child(node.named_child_count().checked_sub(1)?))?;
+                let operator = self.source.get(left.end_byte()..right.start_byte())?.trim();
+                if matches!(
+                    operator,
+                    "==" | "!=" | "<" | "<=" | ">" | ">=" | "&&" | "||"
+                ) {
+                    return value_type("bool");
+                }
+                let left_type = self.expression_type(left);
+                let right_type = self.expression_type(right);
+                if right_type.as_ref().map(|type_| type_.name()) == Some("float") {
+                    return right_type;
+                }
+                left_type.or(right_type)
+            }
+            "sizeof_expression" | "int_literal" => value_type("int"),
+            "float_literal" => value_type("float"),
+            "bool_literal" | "true" | "false" => value_type("bool"),
+            "char_literal" | "string_literal" => value_type("char"),
+            _ => None,
+        }
+    }
+
+    /// Return the method, property or field accessed by a member access, e.g `Length` in
+    /// `view_as<ArrayList>(x).Length`.
+    ///
+    /// # Arguments
+    ///
+    /// * `node` - [Node] of the `field_access` or `scope_access`.
+    pub(crate) fn member_item(&self, node: Node) -> Option<Arc<RwLock<SPItem>>> {
+        let (target_node, field_node) = member_access_nodes(node)?;
+        let target_type = self.expression_type(target_node)?;
+
+        self.member(&target_type, self.text(field_node)?)
+    }
+
+    /// Return the member accessed by the field of a member access, given the [Range] of the
+    /// field, e.g the range of `Length` in `list.Length`.
+    ///
+    /// # Arguments
+    ///
+    /// * `root_node` - Root [Node] of the tree of the document.
+    /// * `range` - [Range] of the field.
+    pub(crate) fn member_item_at(
+        &self,
+        root_node: Node,
+        range: &Range,
+    ) -> Option<Arc<RwLock<SPItem>>> {
+        let field_node = root_node.descendant_for_point_range(
+            Point::new(range.start.line as usize, range.start.character as usize),
+            Point::new(range.end.line as usize, range.end.character as usize),
+        )?;
+
+        self.member_item(field_node.parent()?)
+    }
+
+    /// Return the members of a methodmap, including the inherited ones, or of an enum struct.
+    ///
+    /// # Arguments
+    ///
+    /// * `type_` - [Type](ExpressionType) whose members to return.
+    pub(crate) fn members(&self, type_: &ExpressionType) -> Vec<Arc<RwLock<SPItem>>> {
+        let mut members = vec![];
+        let mut type_item = self.type_item(type_.name());
+        for _ in 0..MAX_INHERITANCE_DEPTH {
+            let Some(item) = type_item else {
+                break;
+            };
+            let item = item.read().unwrap();
+            members.extend(item.children().into_iter().flatten().cloned());
+            type_item = match &*item {
+                SPItem::Methodmap(methodmap_item) => methodmap_item
+                    .parent_name()
+                    .and_then(|parent| self.type_item(&parent)),
+                _ => None,
+            };
+        }
+
+        members
+    }
+
+    /// Return the [item](SPItem) of a methodmap or of an enum struct.
+    ///
+    /// # Arguments
+    ///
+    /// * `name` - Name of the methodmap or enum struct.
+    pub(crate) fn type_item(&self, name: &str) -> Option<Arc<RwLock<SPItem>>> {
+        self.items
+            .get(name)
+            .filter(|item| {
+                matches!(
+                    &*item.read().unwrap(),
+                    SPItem::Methodmap(_) | SPItem::EnumStruct(_)
+                )
+            })
+            .cloned()
+    }
+
+    /// Return whether a type is a methodmap whose whole inheritance chain is known, in which case
+    /// all of its members are known.
+    ///
+    /// # Arguments
+    ///
+    /// * `type_` - [Type](ExpressionType) to check.
+    pub(crate) fn is_known_methodmap(&self, type_: &ExpressionType) -> bool {
+        let mut name = type_.name().to_string();
+        for _ in 0..MAX_INHERITANCE_DEPTH {
+            let Some(item) = self.type_item(&name) else {
+                return false;
+            };
+            let SPItem::Methodmap(methodmap_item) = &*item.read().unwrap() else {
+                return false;
+            };
+            match methodmap_item.parent_name() {
+                Some(parent) => name = parent,
+                None => return true,
+            }
+        }
+
+        false
+    }
+
+    /// Return the member of a type with the given name, looking up the inherited methodmaps.
+    fn member(&self, type_: &ExpressionType, name: &str) -> Option<Arc<RwLock<SPItem>>> {
+        self.members(type_)
+            .into_iter()
+            .find(|member| member.read().unwrap().name() == name)
+    }
+
+    /// Return whether the target of the member access whose field is at a range is a methodmap
+    /// whose members are all known, see [TypeResolver::is_known_methodmap].
+    ///
+    /// # Arguments
+    ///
+    /// * `root_node` - Root [Node] of the tree of the document.
+    /// * `range` - [Range] of the field in the preprocessed text.
+    pub(crate) fn has_known_methodmap_target_at(&self, root_node: Node, range: &Range) -> bool {
+        let Some((target_node, _)) = root_node
+            .descendant_for_point_range(
+                Point::new(range.start.line as usize, range.start.character as usize),
+                Point::new(range.end.line as usize, range.end.character as usize),
+            )
+            .and_then(|field_node| field_node.parent())
+            .and_then(member_access_nodes)
+        else {
+            return false;
+        };
+
+        self.expression_type(target_node)
+            .map_or(false, |target_type| self.is_known_methodmap(&target_type))
+    }
+
+    /// Return the item a symbol refers to, looking up the local variables and the parameters of
+    /// the enclosing function first.
+    ///
+    /// # Arguments
+    ///
+    /// * `node` - [Node] of the symbol.
+    pub(crate) fn symbol_item(&self, node: Node) -> Option<Arc<RwLock<SPItem>>> {
+        let name = self.text(node)?;
+        let range = ts_range_to_lsp_range(&node.range());
+        let local = self
+            .functions
+            .iter()
+            .find(|function| range_contains_range(&function.read().unwrap().full_range(), &range))
+            .and_then(|function| {
+                function
+                    .read()
+                    .unwrap()
+                    .children()?
+                    .iter()
+                    .find(|child| child.read().unwrap().name() == name)
+                    .cloned()
+            });
+
+        local.or_else(|| self.items.get(name).cloned())
+    }
+
+    /// Return the methodmap or enum struct of the document which encloses a node.
+    fn enclosing_type(&self, node: Node) -> Option<Arc<RwLock<SPItem>>> {
+        let range = ts_range_to_lsp_range(&node.range());
+        self.types
+            .iter()
+            .find(|type_item| range_contains_range(&type_item.read().unwrap().full_range(), &range))
+            .cloned()
+    }
+
+    fn text(&self, node: Node) -> Option<&'a str> {
+        node.utf8_text(self.source.as_bytes()).ok()
+    }
+}
+
+/// Return the largest expression of a tree which ends at a position, e.g the receiver of a member
+/// access which is being typed.
+///
+/// # Arguments
+///
+/// * `root_node` - Root [Node] of the tree.
+/// * `end` - [Point] right after the last character of the expression.
+pub(crate) fn expression_ending_at(root_node: Node, end: Point) -> Option<Node> {
+    let last_char = Point::new(end.row, end.column.checked_sub(1)?);
+    let mut node = root_node.descendant_for_point_range(last_char, last_char)?;
+    let mut expression = None;
+    loop {
+        if node.end_position() != end {
+            break;
+        }
+        if EXPRESSION_KINDS.contains(&node.kind()) {
+            expression = Some(node);
+        }
+        node = match node.parent() {
+            Some(parent) => parent,
+            None => break,
+        };
+    }
+
+    expression
+}
+
+/// Return the target and the field of a member access, e.g `list` and `Length` in `list.Length`.
+///
+/// # Arguments
+///
+/// * `node` - [Node] of the `field_access` or `scope_access`.
+pub(crate) fn member_access_nodes(node: Node) -> Option<(Node, Node)> {
+    let target_node = node
+        .child_by_field_name("target")
+        .or_else(|| node.child_by_field_name("scope"))
+        .or_else(|| node.named_child(0))?;
+    let field_node = node
+        .child_by_field_name("field")
+        .or_else(|| node.named_child(node.named_child_count().checked_sub(1)?))?;
+    if field_node == target_node {
+        return None;
+    }
+
+    Some((target_node, field_node))
+}
+
+/// Return the name of a type, without its dimensions or its old style tag colon.
+///
+/// # Arguments
+///
+/// * `type_` - Type of a variable, e.g `KeyValues` or `Handle:`.
+pub(crate) fn type_name(type_: &str) -> String {
+    type_
+        .trim()
+        .chars()
+        .take_while(|c| c.is_alphanumeric() || *c == '_')
+        .collect()
+}
+
+/// Return the type of the values tagged with a type, or [None] for `void` and empty types.
+fn value_type(type_: &str) -> Option<ExpressionType> {
+    let name = type_name(type_);
+    if name.is_empty() || name == "void" {
+        return None;
+    }
+
+    Some(ExpressionType::Value(name))
+}
+
+/// Return the type of the expressions which refer to an item.
+fn item_type(item: &Arc<RwLock<SPItem>>) -> Option<ExpressionType> {
+    match &*item.read().unwrap() {
+        SPItem::Variable(variable_item) if variable_item.type_.is_empty() => value_type("int"),
+        SPItem::Variable(variable_item) => value_type(&variable_item.type_),
+        SPItem::Property(property_item) => value_type(&property_item.type_),
+        SPItem::EnumMember(enum_member_item) => {
+            value_type(&enum_member_item.parent.upgrade()?.read().unwrap().name())
+        }
+        SPItem::Methodmap(methodmap_item) => {
+            Some(ExpressionType::Type(methodmap_item.name.clone()))
+        }
+        SPItem::EnumStruct(enum_struct_item) => {
+            Some(ExpressionType::Type(enum_struct_item.name.clone()))
+        }
+        _ => None,
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::type_name;
+
+    #[test]
+    fn type_name_strips_tags_and_dimensions() {
+        assert_eq!(type_name("KeyValues"), "KeyValues");
+        assert_eq!(type_name("Handle:"), "Handle");
+        assert_eq!(type_name(" int[] "), "int");
+    }
+}
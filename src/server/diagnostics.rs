@@ -1,29 +1,82 @@
+use std::{sync::Arc, thread, time::Duration};
+
+use fxhash::FxHashSet;
 use lsp_types::{
     notification::{PublishDiagnostics, ShowMessage},
-    MessageType, PublishDiagnosticsParams, ShowMessageParams,
+    MessageType, PublishDiagnosticsParams, ShowMessageParams, Url,
 };
 
-use crate::Server;
+use crate::{document::Document, options::SpcompMode, Server};
 
 use super::InternalMessage;
 
+/// Delay before running spcomp on a file, so that successive saves only trigger one run.
+const SPCOMP_DEBOUNCE: Duration = Duration::from_millis(500);
+
 impl Server {
-    /// Reload the diagnostics of the workspace, by running spcomp.
+    /// Reload the diagnostics of the workspace, by running spcomp on each main path. Successive
+    /// reloads only trigger one run per main path.
     pub(crate) fn reload_diagnostics(&mut self) {
+        if self.store.environment.options.spcomp_mode == SpcompMode::PerFile {
+            // The spcomp diagnostics are refreshed when each file is opened or saved.
+            self.lint_all_documents();
+            return;
+        }
         self.store.clear_all_diagnostics();
 
         self.lint_all_documents();
 
-        if let Ok(Some(main_path_uri)) = self.store.environment.options.get_main_path_uri() {
-            // Only reload the diagnostics if the main path is defined.
-            self.spawn(move |mut server| {
-                let _ = server.send_spcomp_status(false);
-                if let Ok(diagnostics_map) = server.store.get_spcomp_diagnostics(main_path_uri) {
+        // Only reload the diagnostics if a main path is defined.
+        for main_path_uri in self.store.main_uris.clone() {
+            self.spawn_spcomp(main_path_uri);
+        }
+    }
+
+    /// Run spcomp on a single `.sp` file if [SpcompMode::PerFile] is enabled. The run starts after
+    /// a short delay and is cancelled if another run is requested for the same file meanwhile.
+    ///
+    /// # Arguments
+    ///
+    /// * `uri` - [Uri](Url) of the file to compile.
+    pub(crate) fn run_spcomp_for_file(&mut self, uri: Url) {
+        let options = &self.store.environment.options;
+        if options.spcomp_mode != SpcompMode::PerFile
+            || options.spcomp_path.as_os_str().is_empty()
+            || !uri.path().ends_with(".sp")
+        {
+            return;
+        }
+        self.spawn_spcomp(uri);
+    }
+
+    /// Run spcomp on a file after a short delay. The run is cancelled if another run is requested
+    /// for the same file meanwhile, e.g when a save is followed by the change event of the file
+    /// watcher.
+    ///
+    /// # Arguments
+    ///
+    /// * `uri` - [Uri](Url) of the file to compile.
+    fn spawn_spcomp(&mut self, uri: Url) {
+        let is_outdated = self.store.start_spcomp_run(&uri);
+        self.spawn(move |mut server| {
+            thread::sleep(SPCOMP_DEBOUNCE);
+            if is_outdated() {
+                return;
+            }
+            let _ = server.send_spcomp_status(false);
+            match server
+                .store
+                .get_spcomp_diagnostics(uri.clone(), is_outdated)
+            {
+                Ok(Some(mut diagnostics_map)) => {
+                    // Make sure the previous diagnostics of the file are cleared.
+                    diagnostics_map.entry(uri).or_default();
                     let _ = server
                         .internal_tx
                         .send(InternalMessage::Diagnostics(diagnostics_map));
-                } else {
-                    // Failed to run spcomp.
+                }
+                Ok(None) => (),
+                Err(_) => {
                     let _ = server
                         .client
                         .send_notification::<ShowMessage>(ShowMessageParams {
@@ -31,34 +84,75 @@ impl Server {
                             typ: MessageType::ERROR,
                         });
                 }
-                let _ = server.send_spcomp_status(true);
-            });
-        }
+            }
+            let _ = server.send_spcomp_status(true);
+        });
     }
 
     /// Lint all documents with the custom linter.
     pub fn lint_all_documents(&mut self) {
-        self.store.clear_all_global_diagnostics();
-        let all_items_flat = self.store.get_all_items(true).0;
-        self.store.get_deprecated_diagnostics(&all_items_flat);
+        let uris: FxHashSet<Arc<Url>> = self.store.documents.keys().cloned().collect();
+        self.lint_documents(&uris);
         let _ = self.publish_diagnostics();
     }
 
+    /// Lint the documents whose diagnostics may change after a document has changed with the
+    /// custom linter, and only publish their diagnostics.
+    ///
+    /// # Arguments
+    ///
+    /// * `uri` - [Uri](Url) of the changed document.
+    pub(super) fn lint_changed_document(&mut self, uri: &Arc<Url>) {
+        let uris = self.store.related_documents(uri);
+        self.lint_documents(&uris);
+        self.publish_documents_diagnostics(&uris);
+    }
+
+    /// Lint some documents with the custom linter.
+    ///
+    /// # Arguments
+    ///
+    /// * `uris` - [Uris](Url) of the documents to lint.
+    fn lint_documents(&mut self, uris: &FxHashSet<Arc<Url>>) {
+        self.store.clear_global_diagnostics(uris);
+        let all_items_flat = self.store.get_all_items(true).0;
+        self.store.get_deprecated_diagnostics(&all_items_flat, uris);
+        self.store.get_unused_diagnostics(uris);
+        self.store.get_undefined_symbol_diagnostics(uris);
+    }
+
     /// Publish all the diagnostics of the store. This will override all diagnostics that have already
     /// been sent to the client.
     pub fn publish_diagnostics(&mut self) -> anyhow::Result<()> {
         for document in self.store.documents.values() {
-            let _ = self
-                .client
-                .send_notification::<PublishDiagnostics>(PublishDiagnosticsParams {
-                    uri: document.uri(),
-                    diagnostics: document
-                        .diagnostics
-                        .all(self.store.environment.options.disable_syntax_linter),
-                    version: None,
-                });
+            self.publish_document_diagnostics(document);
         }
 
         Ok(())
     }
+
+    /// Publish the diagnostics of some documents.
+    ///
+    /// # Arguments
+    ///
+    /// * `uris` - [Uris](Url) of the documents to publish the diagnostics of.
+    fn publish_documents_diagnostics(&self, uris: &FxHashSet<Arc<Url>>) {
+        for document in uris.iter().filter_map(|uri| self.store.documents.get(uri)) {
+            self.publish_document_diagnostics(document);
+        }
+    }
+
+    fn publish_document_diagnostics(&self, document: &Document) {
+        let mut diagnostics = document
+            .diagnostics
+            .all(self.store.environment.options.disable_syntax_linter);
+        diagnostics.extend(document.get_inactive_code_diagnostics());
+        let _ = self
+            .client
+            .send_notification::<PublishDiagnostics>(PublishDiagnosticsParams {
+                uri: document.uri(),
+                diagnostics,
+                version: None,
+            });
+    }
 }
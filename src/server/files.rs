@@ -1,9 +1,11 @@
 use std::{sync::Arc, time::Duration, time::Instant};
 
-use anyhow::{anyhow, Context};
+use anyhow::anyhow;
 use lsp_types::{notification::ShowMessage, MessageType, ShowMessageParams, Url};
 
-use crate::{document::Document, lsp_ext, store::Store, Server};
+use crate::{document::Document, lsp_ext, store::Store, utils::uri_to_file_name, Server};
+
+use super::progress::WorkDoneProgressReporter;
 
 mod events;
 mod watching;
@@ -48,41 +50,54 @@ impl Server {
             quiescent: !self.indexing,
             message: None,
         })?;
-        self.parse_directories();
+        let progress = self.begin_progress("Indexing");
+        self.parse_directories(&progress);
+        let restored_uris = self.store.load_index_cache();
         let main_uri = self.store.environment.options.get_main_path_uri();
+        self.store.main_uris = self.store.environment.options.get_main_paths_uris();
         let now_parse = Instant::now();
         if let Ok(main_uri) = main_uri {
-            if let Some(main_uri) = main_uri {
+            if main_uri.is_some() || !self.store.main_uris.is_empty() {
                 log::debug!("Main path is set, parsing files.");
-                self.parse_files_for_main_path(&main_uri)?;
-            } else if let Some(uri) = self
-                .store
-                .documents
-                .values()
-                .find_map(|document| self.store.is_main_heuristic(document))
-            {
-                log::debug!("Main path was not set, and was infered as {:?}", uri);
-                let path = uri.to_file_path().unwrap();
-                let mut old_options = self.store.environment.options.as_ref().clone();
-                old_options.main_path = path.clone();
-                self.store.environment.options = Arc::new(old_options);
-                self.parse_files_for_main_path(&uri)?;
-                self.client
-                    .send_notification::<ShowMessage>(ShowMessageParams {
-                        message: format!(
-                            "MainPath was not set and was automatically infered as {}.",
-                            path.file_name().unwrap().to_str().unwrap()
-                        ),
-                        typ: MessageType::INFO,
-                    })?;
+                self.parse_files_for_main_paths(self.store.main_uris.clone(), &progress)?;
             } else {
-                log::debug!("Main path was not set, and could not be infered.");
-                self.client
-                    .send_notification::<ShowMessage>(ShowMessageParams {
-                        message: "No MainPath setting and none could be infered.".to_string(),
-                        typ: MessageType::WARNING,
-                    })?;
-                self.parse_files_for_missing_main_path();
+                let uris: Vec<Url> = self
+                    .store
+                    .documents
+                    .values()
+                    .filter_map(|document| self.store.is_main_heuristic(document))
+                    .collect();
+                if let Some(uri) = uris.first() {
+                    log::debug!("Main path was not set, and was infered as {:?}", uris);
+                    let path = uri.to_file_path().unwrap();
+                    let mut old_options = self.store.environment.options.as_ref().clone();
+                    old_options.main_path = path;
+                    self.store.environment.options = Arc::new(old_options);
+                    self.store.main_uris = uris.clone();
+                    self.parse_files_for_main_paths(uris.clone(), &progress)?;
+                    let file_names: Vec<String> = uris
+                        .iter()
+                        .filter_map(|uri| {
+                            Some(uri.to_file_path().ok()?.file_name()?.to_str()?.to_string())
+                        })
+                        .collect();
+                    self.client
+                        .send_notification::<ShowMessage>(ShowMessageParams {
+                            message: format!(
+                                "MainPath was not set and was automatically infered as {}.",
+                                file_names.join(", ")
+                            ),
+                            typ: MessageType::INFO,
+                        })?;
+                } else {
+                    log::debug!("Main path was not set, and could not be infered.");
+                    self.client
+                        .send_notification::<ShowMessage>(ShowMessageParams {
+                            message: "No MainPath setting and none could be infered.".to_string(),
+                            typ: MessageType::WARNING,
+                        })?;
+                    self.parse_files_for_missing_main_path(&progress);
+                }
             }
         } else if main_uri.is_err() {
             log::debug!("Main path is invalid.");
@@ -91,10 +106,20 @@ impl Server {
                     message: "Invalid MainPath setting.".to_string(),
                     typ: MessageType::WARNING,
                 })?;
-            self.parse_files_for_missing_main_path();
+            self.parse_files_for_missing_main_path(&progress);
+        }
+        if self.store.first_parse {
+            // The references have not been resolved yet, the documents can be cached.
+            self.store.save_index_cache(&restored_uris);
         }
         let now_analysis = Instant::now();
-        self.store.find_all_references();
+        self.store.find_all_references(|uri, done, total| {
+            progress.report(
+                &format!("Analyzing {}", uri_to_file_name(uri).unwrap_or_default()),
+                done,
+                total,
+            )
+        });
         self.store.first_parse = false;
         let parse_duration = now_parse.elapsed();
         let analysis_duration = now_analysis.elapsed();
@@ -119,6 +144,7 @@ impl Server {
             get_includes_duration,
             analysis_get_items_duration - get_includes_duration
         );
+        drop(progress);
         self.indexing = false;
         self.reload_diagnostics();
         self.send_status(lsp_ext::ServerStatusParams {
@@ -130,41 +156,44 @@ impl Server {
         Ok(())
     }
 
-    fn parse_files_for_missing_main_path(&mut self) {
-        let mut uris: Vec<Url> = vec![];
-        for uri in self.store.documents.keys() {
-            uris.push(uri.as_ref().clone());
-        }
-        for uri in uris.iter() {
-            let document = self.store.get(uri);
-            if let Some(document) = document {
-                match self.store.handle_open_document(
-                    &document.uri,
-                    document.text,
-                    &mut self.parser,
-                ) {
-                    Ok(_) => {}
-                    Err(error) => {
-                        log::error!("Error while parsing file: {}", error);
-                    }
-                }
+    fn parse_files_for_missing_main_path(&mut self, progress: &WorkDoneProgressReporter) {
+        let uris: Vec<Arc<Url>> = self.store.documents.keys().cloned().collect();
+        self.parse_files(uris, progress);
+    }
+
+    fn parse_files_for_main_paths(
+        &mut self,
+        main_uris: Vec<Url>,
+        progress: &WorkDoneProgressReporter,
+    ) -> anyhow::Result<()> {
+        for main_uri in main_uris.iter() {
+            if !self.store.documents.contains_key(main_uri) {
+                return Err(anyhow!("Main Path does not exist at uri {:?}", main_uri));
             }
         }
+        self.parse_files(main_uris.into_iter().map(Arc::new).collect(), progress);
+
+        Ok(())
     }
 
-    fn parse_files_for_main_path(&mut self, main_uri: &Url) -> anyhow::Result<()> {
-        let document = self
-            .store
-            .get(main_uri)
-            .context(format!("Main Path does not exist at uri {:?}", main_uri))?;
+    /// Parse documents and their includes on the thread pool, and report each parsed file.
+    ///
+    /// # Arguments
+    ///
+    /// * `uris` - [Uris](Url) of the documents to parse.
+    /// * `progress` - [Progress](WorkDoneProgressReporter) of the indexing.
+    fn parse_files(&mut self, uris: Vec<Arc<Url>>, progress: &WorkDoneProgressReporter) {
         self.store
-            .handle_open_document(&document.uri, document.text, &mut self.parser)
-            .context(format!("Could not parse file at uri {:?}", main_uri))?;
-
-        Ok(())
+            .parse_concurrently(uris, &self.pool, |uri, done, total| {
+                progress.report(
+                    &format!("Parsing {}", uri_to_file_name(uri).unwrap_or_default()),
+                    done,
+                    total,
+                );
+            });
     }
 
-    fn parse_directories(&mut self) {
+    fn parse_directories(&mut self, progress: &WorkDoneProgressReporter) {
         let directories = self.store.environment.options.includes_directories.clone();
         for path in directories {
             if !path.exists() {
@@ -179,6 +208,7 @@ impl Server {
                     .unwrap_or_default();
                 continue;
             }
+            progress.report_message(format!("Scanning {}", path.display()));
             self.store.find_documents(&path);
         }
     }
@@ -83,6 +83,7 @@ impl Server {
                                 .store
                                 .reload(uri.to_file_path().unwrap(), &mut self.parser);
                         }
+                        self.run_spcomp_for_file(uri);
                     }
                 }
                 self.reload_diagnostics();
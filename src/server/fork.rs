@@ -73,6 +73,20 @@ impl ServerFork {
         };
 
         if let Some(mut options) = options {
+            if let Some(root_path) = self
+                .store
+                .environment
+                .root_uri
+                .as_ref()
+                .and_then(|root_uri| root_uri.to_file_path().ok())
+            {
+                // Try to resolve the main paths as relative.
+                for main_path in options.main_paths.iter_mut() {
+                    if !main_path.is_absolute() {
+                        *main_path = root_path.join(main_path.as_path());
+                    }
+                }
+            }
             if options.main_path.is_absolute() || options.main_path.to_str().unwrap().is_empty() {
                 return Ok(options);
             }
@@ -87,11 +101,10 @@ impl ServerFork {
     }
 
     pub fn feature_request<P>(&self, uri: Arc<Url>, params: P) -> FeatureRequest<P> {
-        FeatureRequest {
-            params,
-            store: self.store.clone(),
-            uri,
-        }
+        let mut store = self.store.clone();
+        // Resolve the items against the plugin the document belongs to.
+        store.scoped_main_uri = store.main_uri_of(&uri);
+        FeatureRequest { params, store, uri }
     }
 
     pub(crate) fn send_spcomp_status(&self, quiescent: bool) -> anyhow::Result<()> {
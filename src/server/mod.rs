@@ -1,15 +1,23 @@
 use crate::{linter::spcomp::SPCompDiagnostic, lsp_ext, options::Options, store::Store};
-use std::sync::Arc;
+use std::{
+    collections::VecDeque,
+    sync::{Arc, Mutex},
+};
 
 use crossbeam_channel::{Receiver, Sender};
 use fxhash::FxHashMap;
 use lsp_server::{Connection, Message};
 use lsp_types::{
-    CallHierarchyServerCapability, CompletionOptions, CompletionOptionsCompletionItem,
-    HoverProviderCapability, InitializeParams, InitializeResult, OneOf, SemanticTokenModifier,
+    CallHierarchyServerCapability, CodeActionProviderCapability, CompletionOptions,
+    CompletionOptionsCompletionItem, DeclarationCapability, FileOperationFilter,
+    FileOperationPattern, FileOperationPatternKind, FileOperationRegistrationOptions,
+    FoldingRangeProviderCapability, HoverProviderCapability, ImplementationProviderCapability,
+    InitializeParams, InitializeResult, OneOf, RenameOptions, SaveOptions, SemanticTokenModifier,
     SemanticTokenType, SemanticTokensFullOptions, SemanticTokensLegend, SemanticTokensOptions,
     SemanticTokensServerCapabilities, ServerCapabilities, ServerInfo, SignatureHelpOptions,
-    TextDocumentSyncCapability, TextDocumentSyncKind, Url, WorkDoneProgressOptions,
+    TextDocumentSyncCapability, TextDocumentSyncKind, TextDocumentSyncOptions,
+    TextDocumentSyncSaveOptions, TypeDefinitionProviderCapability, Url, WorkDoneProgressOptions,
+    WorkspaceFileOperationsServerCapabilities, WorkspaceServerCapabilities,
 };
 
 use threadpool::ThreadPool;
@@ -23,6 +31,7 @@ mod diagnostics;
 mod files;
 mod fork;
 mod notifications;
+mod progress;
 mod requests;
 
 #[derive(Debug)]
@@ -42,6 +51,7 @@ pub struct Server {
     parser: Parser,
     config_pulled: bool,
     indexing: bool,
+    deferred_messages: Arc<Mutex<VecDeque<Message>>>,
 }
 
 impl Server {
@@ -62,6 +72,7 @@ impl Server {
             parser,
             config_pulled: false,
             indexing: false,
+            deferred_messages: Default::default(),
         }
     }
 
@@ -69,9 +80,38 @@ impl Server {
         let (id, params) = self.connection.initialize_start()?;
         let params: InitializeParams = serde_json::from_value(params)?;
 
+        // Rewrite the includes when SourcePawn files, or the folders which contain them, are
+        // renamed.
+        let file_rename_options = FileOperationRegistrationOptions {
+            filters: vec![
+                FileOperationFilter {
+                    scheme: Some("file".to_string()),
+                    pattern: FileOperationPattern {
+                        glob: "**/*.{sp,inc,sma}".to_string(),
+                        matches: Some(FileOperationPatternKind::File),
+                        options: None,
+                    },
+                },
+                FileOperationFilter {
+                    scheme: Some("file".to_string()),
+                    pattern: FileOperationPattern {
+                        glob: "**".to_string(),
+                        matches: Some(FileOperationPatternKind::Folder),
+                        options: None,
+                    },
+                },
+            ],
+        };
         let capabilities = ServerCapabilities {
-            text_document_sync: Some(TextDocumentSyncCapability::Kind(
-                TextDocumentSyncKind::INCREMENTAL,
+            text_document_sync: Some(TextDocumentSyncCapability::Options(
+                TextDocumentSyncOptions {
+                    open_close: Some(true),
+                    change: Some(TextDocumentSyncKind::INCREMENTAL),
+                    save: Some(TextDocumentSyncSaveOptions::SaveOptions(SaveOptions {
+                        include_text: Some(false),
+                    })),
+                    ..Default::default()
+                },
             )),
             completion_provider: Some(CompletionOptions {
                 trigger_characters: Some(vec![
@@ -94,6 +134,9 @@ impl Server {
             }),
             hover_provider: Some(HoverProviderCapability::Simple(true)),
             definition_provider: Some(OneOf::Left(true)),
+            type_definition_provider: Some(TypeDefinitionProviderCapability::Simple(true)),
+            implementation_provider: Some(ImplementationProviderCapability::Simple(true)),
+            declaration_provider: Some(DeclarationCapability::Simple(true)),
             signature_help_provider: Some(SignatureHelpOptions {
                 trigger_characters: Some(vec![",".to_string(), "(".to_string()]),
                 retrigger_characters: Some(vec![",".to_string(), "(".to_string()]),
@@ -101,7 +144,13 @@ impl Server {
             }),
             references_provider: Some(OneOf::Left(true)),
             document_symbol_provider: Some(OneOf::Left(true)),
-            rename_provider: Some(OneOf::Left(true)),
+            document_highlight_provider: Some(OneOf::Left(true)),
+            rename_provider: Some(OneOf::Right(RenameOptions {
+                prepare_provider: Some(true),
+                work_done_progress_options: WorkDoneProgressOptions {
+                    work_done_progress: None,
+                },
+            })),
             semantic_tokens_provider: Some(
                 SemanticTokensServerCapabilities::SemanticTokensOptions(SemanticTokensOptions {
                     work_done_progress_options: WorkDoneProgressOptions {
@@ -126,11 +175,25 @@ impl Server {
                             SemanticTokenModifier::MODIFICATION,
                         ],
                     },
-                    range: Some(false),
-                    full: Some(SemanticTokensFullOptions::Delta { delta: Some(false) }),
+                    range: Some(true),
+                    full: Some(SemanticTokensFullOptions::Delta { delta: Some(true) }),
                 }),
             ),
             call_hierarchy_provider: Some(CallHierarchyServerCapability::Simple(true)),
+            document_formatting_provider: Some(OneOf::Left(true)),
+            document_range_formatting_provider: Some(OneOf::Left(true)),
+            workspace_symbol_provider: Some(OneOf::Left(true)),
+            code_action_provider: Some(CodeActionProviderCapability::Simple(true)),
+            inlay_hint_provider: Some(OneOf::Left(true)),
+            folding_range_provider: Some(FoldingRangeProviderCapability::Simple(true)),
+            workspace: Some(WorkspaceServerCapabilities {
+                workspace_folders: None,
+                file_operations: Some(WorkspaceFileOperationsServerCapabilities {
+                    will_rename: Some(file_rename_options.clone()),
+                    did_rename: Some(file_rename_options),
+                    ..Default::default()
+                }),
+            }),
             ..Default::default()
         };
         let result = InitializeResult {
@@ -140,8 +203,10 @@ impl Server {
                 version: Some(env!("CARGO_PKG_VERSION").to_owned()),
             }),
         };
-        self.connection
-            .initialize_finish(id, serde_json::to_value(result)?)?;
+        let mut result = serde_json::to_value(result)?;
+        // lsp-types does not expose the type hierarchy capability yet.
+        result["capabilities"]["typeHierarchyProvider"] = serde_json::Value::Bool(true);
+        self.connection.initialize_finish(id, result)?;
 
         self.store.environment.client_capabilities = Arc::new(params.capabilities);
         self.store.environment.client_info = params.client_info.map(Arc::new);
@@ -183,49 +248,78 @@ impl Server {
         }
     }
 
+    /// Handle a message received from the client.
+    ///
+    /// Return `true` if the message was the shutdown request.
+    ///
+    /// # Arguments
+    ///
+    /// * `msg` - [Message] to handle.
+    fn handle_message(&mut self, msg: Message) -> anyhow::Result<bool> {
+        match msg {
+            Message::Request(request) => {
+                log::trace!("Received request {:#?}", request);
+                if self.connection.handle_shutdown(&request)? {
+                    log::trace!("Handled shutdown request.");
+                    return Ok(true);
+                }
+                if let Err(error) = self.handle_request(request) {
+                    self.send_status(lsp_ext::ServerStatusParams {
+                        health: crate::lsp_ext::Health::Error,
+                        quiescent: !self.indexing,
+                        message: Some(error.to_string()),
+                    })?;
+                }
+            }
+            Message::Response(resp) => {
+                if let Err(error) = self.client.recv_response(resp) {
+                    self.send_status(lsp_ext::ServerStatusParams {
+                        health: crate::lsp_ext::Health::Error,
+                        quiescent: !self.indexing,
+                        message: Some(error.to_string()),
+                    })?;
+                }
+            }
+            Message::Notification(notification) => {
+                if let Err(error) = self.handle_notification(notification) {
+                    self.send_status(lsp_ext::ServerStatusParams {
+                        health: crate::lsp_ext::Health::Error,
+                        quiescent: !self.indexing,
+                        message: Some(error.to_string()),
+                    })?;
+                }
+            }
+        }
+
+        Ok(false)
+    }
+
     fn process_messages(&mut self) -> anyhow::Result<()> {
         loop {
+            // Handle the messages received while the server was waiting for a response first.
+            let deferred_msg = self.deferred_messages.lock().unwrap().pop_front();
+            if let Some(msg) = deferred_msg {
+                if self.handle_message(msg)? {
+                    return Ok(());
+                }
+                continue;
+            }
             crossbeam_channel::select! {
                 recv(&self.connection.receiver) -> msg => {
-                        match msg? {
-                            Message::Request(request) => {
-                                log::trace!("Received request {:#?}", request);
-                                if self.connection.handle_shutdown(&request)? {
-                                    log::trace!("Handled shutdown request.");
-                                    return Ok(());
-                                }
-                                if let Err(error) = self.handle_request(request) {
-                                    self.send_status(lsp_ext::ServerStatusParams {
-                                        health: crate::lsp_ext::Health::Error,
-                                        quiescent: !self.indexing,
-                                        message: Some(error.to_string()),
-                                    })?;
-                                }
-                            }
-                            Message::Response(resp) => {
-                                if let Err(error) = self.client.recv_response(resp) {
-                                    self.send_status(lsp_ext::ServerStatusParams {
-                                        health: crate::lsp_ext::Health::Error,
-                                        quiescent: !self.indexing,
-                                        message: Some(error.to_string()),
-                                    })?;
-                                }
-                            }
-                            Message::Notification(notification) => {
-                                if let Err(error) = self.handle_notification(notification) {
-                                    self.send_status(lsp_ext::ServerStatusParams {
-                                        health: crate::lsp_ext::Health::Error,
-                                        quiescent: !self.indexing,
-                                        message: Some(error.to_string()),
-                                    })?;
-                                }
-                            }
+                        if self.handle_message(msg?)? {
+                            return Ok(());
                         }
                     }
                     recv(&self.internal_rx) -> msg => {
                         match msg? {
                             InternalMessage::SetOptions(options) => {
                                 self.config_pulled = true;
+                                if options.predefined_macros
+                                    != self.store.environment.options.predefined_macros
+                                {
+                                    // The macros change the preprocessed text of every document.
+                                    self.store.invalidate_preprocessing();
+                                }
                                 self.store.environment.options = options;
                                 self.register_file_watching()?;
                                 self.reparse_all().expect("Failed to reparse all files.");
@@ -1,14 +1,23 @@
-use crate::{capabilities::ClientCapabilitiesExt, dispatch, document::Document, utils};
-use std::sync::Arc;
+use crate::{
+    capabilities::ClientCapabilitiesExt,
+    dispatch,
+    document::Document,
+    options::SpcompMode,
+    providers::file_rename::{renamed_path, renamed_paths},
+    utils,
+};
+use std::{path::PathBuf, sync::Arc};
 
 use crate::Server;
 use lsp_server::Notification;
 use lsp_types::{
     notification::{
-        DidChangeConfiguration, DidChangeTextDocument, DidChangeWatchedFiles, DidOpenTextDocument,
+        DidChangeConfiguration, DidChangeTextDocument, DidChangeWatchedFiles, DidCloseTextDocument,
+        DidOpenTextDocument, DidRenameFiles, DidSaveTextDocument,
     },
     DidChangeConfigurationParams, DidChangeTextDocumentParams, DidChangeWatchedFilesParams,
-    DidOpenTextDocumentParams, FileChangeType,
+    DidCloseTextDocumentParams, DidOpenTextDocumentParams, DidSaveTextDocumentParams,
+    FileChangeType, RenameFilesParams, Url,
 };
 
 impl Server {
@@ -24,6 +33,8 @@ impl Server {
             return Ok(());
         }
 
+        self.run_spcomp_for_file(uri.as_ref().clone());
+
         // Don't parse the document if it has already been opened.
         // GoToDefinition request will trigger a new parse.
         if let Some(document) = self.store.documents.get(&uri) {
@@ -62,7 +73,35 @@ impl Server {
             },
         };
 
-        self.lint_all_documents();
+        self.lint_changed_document(&uri);
+
+        Ok(())
+    }
+
+    pub(super) fn did_close(
+        &mut self,
+        mut params: DidCloseTextDocumentParams,
+    ) -> anyhow::Result<()> {
+        utils::normalize_uri(&mut params.text_document.uri);
+        let uri = Arc::new(params.text_document.uri);
+
+        self.store.close(&uri, &mut self.parser)?;
+        if !self.store.main_uris.contains(&uri) {
+            // The main paths are compiled for the whole workspace, keep their runs going.
+            self.store.cancel_spcomp_runs(&uri);
+        }
+        self.lint_changed_document(&uri);
+
+        Ok(())
+    }
+
+    pub(super) fn did_save(&mut self, mut params: DidSaveTextDocumentParams) -> anyhow::Result<()> {
+        utils::normalize_uri(&mut params.text_document.uri);
+
+        match self.store.environment.options.spcomp_mode {
+            SpcompMode::MainPath => self.reload_diagnostics(),
+            SpcompMode::PerFile => self.run_spcomp_for_file(params.text_document.uri),
+        }
 
         Ok(())
     }
@@ -79,6 +118,7 @@ impl Server {
                         .store
                         .reload(change.uri.to_file_path().unwrap(), &mut self.parser);
                     self.reload_diagnostics();
+                    self.run_spcomp_for_file(change.uri);
                 }
                 FileChangeType::DELETED => {
                     self.store.remove(&change.uri, &mut self.parser);
@@ -99,6 +139,61 @@ impl Server {
         Ok(())
     }
 
+    pub(super) fn did_rename_files(&mut self, params: RenameFilesParams) -> anyhow::Result<()> {
+        let renames = renamed_paths(&params);
+        // Files can be moved along with their folder.
+        let moved_documents: Vec<(Url, PathBuf)> = self
+            .store
+            .documents
+            .keys()
+            .filter_map(|uri| {
+                let new_path = renamed_path(&uri.to_file_path().ok()?, &renames)?;
+                Some(((**uri).clone(), new_path))
+            })
+            .collect();
+        let including_uris: Vec<Arc<Url>> = self
+            .store
+            .documents
+            .values()
+            .filter(|document| {
+                moved_documents.iter().all(|(uri, _)| *uri != *document.uri)
+                    && moved_documents
+                        .iter()
+                        .any(|(uri, _)| document.includes.contains_key(uri))
+            })
+            .map(|document| document.uri.clone())
+            .collect();
+        // The quoted includes of the moved documents are resolved from their main path.
+        for main_uri in self.store.main_uris.iter_mut() {
+            if let Some(new_uri) = main_uri
+                .to_file_path()
+                .ok()
+                .and_then(|path| renamed_path(&path, &renames))
+                .and_then(|new_path| Url::from_file_path(new_path).ok())
+            {
+                *main_uri = new_uri;
+            }
+        }
+        for (uri, _) in moved_documents.iter() {
+            self.store.remove(uri, &mut self.parser);
+        }
+        for (_, new_path) in moved_documents {
+            let _ = self.store.load(new_path, &mut self.parser);
+        }
+        // Resolve the includes of the documents which included the moved files again, with
+        // their current text as they may not be saved.
+        for uri in including_uris {
+            if let Some(text) = self.store.get_text(&uri) {
+                let _ = self
+                    .store
+                    .handle_open_document(&uri, text, &mut self.parser);
+            }
+        }
+        self.reload_diagnostics();
+
+        Ok(())
+    }
+
     pub(super) fn did_change_configuration(
         &mut self,
         params: DidChangeConfigurationParams,
@@ -126,8 +221,11 @@ impl Server {
         dispatch::NotificationDispatcher::new(notification)
             .on::<DidOpenTextDocument, _>(|params| self.did_open(params))?
             .on::<DidChangeTextDocument, _>(|params| self.did_change(params))?
+            .on::<DidCloseTextDocument, _>(|params| self.did_close(params))?
+            .on::<DidSaveTextDocument, _>(|params| self.did_save(params))?
             .on::<DidChangeConfiguration, _>(|params| self.did_change_configuration(params))?
             .on::<DidChangeWatchedFiles, _>(|params| self.did_change_watched_files(params))?
+            .on::<DidRenameFiles, _>(|params| self.did_rename_files(params))?
             .default();
 
         Ok(())
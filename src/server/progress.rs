@@ -0,0 +1,374 @@
+use std::{
+    collections::VecDeque,
+    sync::{
+        atomic::{AtomicU32, Ordering},
+        Arc, Mutex,
+    },
+    time::{Duration, Instant},
+};
+
+use crossbeam_channel::{Receiver, TryRecvError};
+use lsp_server::{Message, RequestId, Response};
+use lsp_types::{
+    notification::Progress, request::WorkDoneProgressCreate, NumberOrString, ProgressParams,
+    ProgressParamsValue, WorkDoneProgress, WorkDoneProgressBegin, WorkDoneProgressCreateParams,
+    WorkDoneProgressEnd, WorkDoneProgressReport,
+};
+
+use crate::{capabilities::ClientCapabilitiesExt, client::LspClient, Server};
+
+/// Id of the next work done progress token.
+static NEXT_TOKEN_ID: AtomicU32 = AtomicU32::new(0);
+
+/// Delay after which a work done progress which was not created by the client is dropped.
+const CREATE_TIMEOUT: Duration = Duration::from_secs(5);
+
+/// Creation of a work done progress which the client has not replied to yet.
+struct PendingCreation {
+    /// Id of the `window/workDoneProgress/create` request.
+    id: RequestId,
+
+    /// Token of the progress to create.
+    token: NumberOrString,
+
+    /// Receiver of the response of the client.
+    response_rx: Receiver<Response>,
+
+    /// Receiver of the messages of the client, to route the response while the server is busy.
+    receiver: Receiver<Message>,
+
+    /// Messages received while waiting for the response, handled once the server is idle.
+    deferred_messages: Arc<Mutex<VecDeque<Message>>>,
+
+    /// Time after which the creation is abandoned.
+    deadline: Instant,
+}
+
+/// State of a [WorkDoneProgressReporter].
+enum ProgressState {
+    /// The creation was requested, the progress begins once the client replies.
+    Pending(PendingCreation),
+
+    /// The progress began with this token.
+    Begun(NumberOrString),
+
+    /// Nothing is reported, the client does not support work done progress or failed to
+    /// create it.
+    Disabled,
+}
+
+/// Work done progress displayed by the client, through `window/workDoneProgress/create` and
+/// `$/progress`. The server keeps working while the client creates the progress, the steps
+/// are only reported once it has been created. The progress ends when the reporter is dropped.
+pub(super) struct WorkDoneProgressReporter {
+    client: LspClient,
+    title: String,
+    state: Mutex<ProgressState>,
+}
+
+impl WorkDoneProgressReporter {
+    /// Report the progress of the current step, e.g `Parsing sourcemod.inc (37/412)`.
+    ///
+    /// # Arguments
+    ///
+    /// * `message` - Description of the current step.
+    /// * `done` - Number of items which have been handled.
+    /// * `total` - Total number of items to handle.
+    pub(super) fn report(&self, message: &str, done: usize, total: usize) {
+        let total = total.max(done).max(1);
+        self.send(WorkDoneProgress::Report(WorkDoneProgressReport {
+            cancellable: Some(false),
+            message: Some(format!("{} ({}/{})", message, done, total)),
+            percentage: Some((done * 100 / total) as u32),
+        }));
+    }
+
+    /// Report a step which can't be measured.
+    ///
+    /// # Arguments
+    ///
+    /// * `message` - Description of the current step.
+    pub(super) fn report_message(&self, message: String) {
+        self.send(WorkDoneProgress::Report(WorkDoneProgressReport {
+            cancellable: Some(false),
+            message: Some(message),
+            percentage: None,
+        }));
+    }
+
+    /// Send a value of the progress if it has begun, after checking whether the client replied
+    /// to its creation.
+    fn send(&self, value: WorkDoneProgress) {
+        let mut state = self.state.lock().unwrap();
+        if let ProgressState::Pending(pending) = &*state {
+            if let Some(new_state) = self.poll_creation(pending) {
+                *state = new_state;
+            }
+        }
+        if let ProgressState::Begun(token) = &*state {
+            let _ = self.client.send_notification::<Progress>(ProgressParams {
+                token: token.clone(),
+                value: ProgressParamsValue::WorkDone(value),
+            });
+        }
+    }
+
+    /// Route the messages received since the last call, and return the new state of the
+    /// progress once the client replied to its creation, or once it timed out.
+    ///
+    /// # Arguments
+    ///
+    /// * `pending` - Creation of the progress.
+    fn poll_creation(&self, pending: &PendingCreation) -> Option<ProgressState> {
+        while let Ok(msg) = pending.receiver.try_recv() {
+            match msg {
+                Message::Response(resp) => {
+                    let _ = self.client.recv_response(resp);
+                }
+                msg => pending.deferred_messages.lock().unwrap().push_back(msg),
+            }
+        }
+        match pending.response_rx.try_recv() {
+            Ok(response) => {
+                if let Some(error) = response.error {
+                    log::warn!("Failed to create a work done progress: {}", error.message);
+                    return Some(ProgressState::Disabled);
+                }
+                let _ = self.client.send_notification::<Progress>(ProgressParams {
+                    token: pending.token.clone(),
+                    value: ProgressParamsValue::WorkDone(WorkDoneProgress::Begin(
+                        WorkDoneProgressBegin {
+                            title: self.title.clone(),
+                            cancellable: Some(false),
+                            message: None,
+                            percentage: Some(0),
+                        },
+                    )),
+                });
+                Some(ProgressState::Begun(pending.token.clone()))
+            }
+            Err(TryRecvError::Empty) if Instant::now() < pending.deadline => None,
+            Err(TryRecvError::Empty) => {
+                log::warn!("The client did not reply to the creation of a work done progress.");
+                self.client.forget_request(&pending.id);
+                Some(ProgressState::Disabled)
+            }
+            Err(TryRecvError::Disconnected) => Some(ProgressState::Disabled),
+        }
+    }
+}
+
+impl Drop for WorkDoneProgressReporter {
+    fn drop(&mut self) {
+        let state = std::mem::replace(self.state.get_mut().unwrap(), ProgressState::Disabled);
+        match state {
+            ProgressState::Begun(token) => {
+                let _ = self.client.send_notification::<Progress>(ProgressParams {
+                    token,
+                    value: ProgressParamsValue::WorkDone(WorkDoneProgress::End(
+                        WorkDoneProgressEnd { message: None },
+                    )),
+                });
+            }
+            ProgressState::Pending(pending) => {
+                // The work is over, the progress does not need to begin anymore.
+                self.client.forget_request(&pending.id);
+            }
+            ProgressState::Disabled => (),
+        }
+    }
+}
+
+impl Server {
+    /// Request the creation of a work done progress on the client, without waiting for its
+    /// response. Nothing is reported if the client does not support work done progress, or if
+    /// it fails to create it.
+    ///
+    /// The requests and notifications received while the creation is pending are deferred to
+    /// [Server::process_messages], as the server may be in the middle of the indexing.
+    ///
+    /// # Arguments
+    ///
+    /// * `title` - Title of the progress.
+    pub(super) fn begin_progress(&self, title: &str) -> WorkDoneProgressReporter {
+        let state = if self
+            .store
+            .environment
+            .client_capabilities
+            .has_work_done_progress_support()
+        {
+            self.create_progress()
+        } else {
+            ProgressState::Disabled
+        };
+
+        WorkDoneProgressReporter {
+            client: self.client.clone(),
+            title: title.to_string(),
+            state: Mutex::new(state),
+        }
+    }
+
+    fn create_progress(&self) -> ProgressState {
+        let token = NumberOrString::String(format!(
+            "sourcepawn-lsp/{}",
+            NEXT_TOKEN_ID.fetch_add(1, Ordering::SeqCst)
+        ));
+        let Ok((id, response_rx)) = self.client.send_request_async::<WorkDoneProgressCreate>(
+            WorkDoneProgressCreateParams {
+                token: token.clone(),
+            },
+        ) else {
+            return ProgressState::Disabled;
+        };
+
+        ProgressState::Pending(PendingCreation {
+            id,
+            token,
+            response_rx,
+            receiver: self.connection.receiver.clone(),
+            deferred_messages: self.deferred_messages.clone(),
+            deadline: Instant::now() + CREATE_TIMEOUT,
+        })
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use std::{
+        sync::Mutex,
+        time::{Duration, Instant},
+    };
+
+    use crossbeam_channel::{Receiver, Sender};
+    use lsp_server::{ErrorCode, Message, Notification, Response};
+    use lsp_types::{
+        notification::{Notification as _, Progress},
+        request::WorkDoneProgressCreate,
+        NumberOrString, WorkDoneProgressCreateParams,
+    };
+
+    use super::{PendingCreation, ProgressState, WorkDoneProgressReporter};
+    use crate::client::LspClient;
+
+    /// Build a reporter whose creation is pending, with the receiver of the messages sent to
+    /// the client and the sender of the messages received from the client.
+    fn pending_reporter(
+        timeout: Duration,
+    ) -> (
+        WorkDoneProgressReporter,
+        lsp_server::RequestId,
+        Receiver<Message>,
+        Sender<Message>,
+    ) {
+        let (client_tx, client_rx) = crossbeam_channel::unbounded();
+        let (server_tx, server_rx) = crossbeam_channel::unbounded();
+        let client = LspClient::new(client_tx);
+        let token = NumberOrString::String("test".to_string());
+        let (id, response_rx) = client
+            .send_request_async::<WorkDoneProgressCreate>(WorkDoneProgressCreateParams {
+                token: token.clone(),
+            })
+            .unwrap();
+        // Skip the creation request.
+        client_rx.recv().unwrap();
+        let reporter = WorkDoneProgressReporter {
+            client,
+            title: "Indexing".to_string(),
+            state: Mutex::new(ProgressState::Pending(PendingCreation {
+                id: id.clone(),
+                token,
+                response_rx,
+                receiver: server_rx,
+                deferred_messages: Default::default(),
+                deadline: Instant::now() + timeout,
+            })),
+        };
+
+        (reporter, id, client_rx, server_tx)
+    }
+
+    fn progress_kinds(client_rx: &Receiver<Message>) -> Vec<String> {
+        client_rx
+            .try_iter()
+            .map(|msg| match msg {
+                Message::Notification(notification) => {
+                    assert_eq!(notification.method, Progress::METHOD);
+                    notification.params["value"]["kind"]
+                        .as_str()
+                        .unwrap()
+                        .to_string()
+                }
+                msg => panic!("Unexpected message {:?}", msg),
+            })
+            .collect()
+    }
+
+    #[test]
+    fn progress_begins_once_created() {
+        let (reporter, id, client_rx, server_tx) = pending_reporter(Duration::from_secs(60));
+        reporter.report("Parsing", 1, 2);
+        assert!(progress_kinds(&client_rx).is_empty());
+
+        server_tx.send(Response::new_ok(id, ()).into()).unwrap();
+        reporter.report("Parsing", 2, 2);
+        assert!(matches!(
+            &*reporter.state.lock().unwrap(),
+            ProgressState::Begun(_)
+        ));
+        assert_eq!(progress_kinds(&client_rx), vec!["begin", "report"]);
+
+        drop(reporter);
+        assert_eq!(progress_kinds(&client_rx), vec!["end"]);
+    }
+
+    #[test]
+    fn progress_defers_the_other_messages() {
+        let (reporter, id, _client_rx, server_tx) = pending_reporter(Duration::from_secs(60));
+        let deferred_messages = match &*reporter.state.lock().unwrap() {
+            ProgressState::Pending(pending) => pending.deferred_messages.clone(),
+            _ => unreachable!(),
+        };
+        server_tx
+            .send(Notification::new("initialized".to_string(), ()).into())
+            .unwrap();
+        server_tx.send(Response::new_ok(id, ()).into()).unwrap();
+        reporter.report_message("Scanning".to_string());
+
+        let deferred_messages = deferred_messages.lock().unwrap();
+        assert_eq!(deferred_messages.len(), 1);
+        assert!(matches!(
+            &deferred_messages[0],
+            Message::Notification(notification) if notification.method == "initialized"
+        ));
+    }
+
+    #[test]
+    fn progress_is_dropped_when_creation_fails() {
+        let (reporter, id, client_rx, server_tx) = pending_reporter(Duration::from_secs(60));
+        server_tx
+            .send(
+                Response::new_err(id, ErrorCode::InternalError as i32, "Failed".to_string()).into(),
+            )
+            .unwrap();
+        reporter.report("Parsing", 1, 2);
+        drop(reporter);
+        assert!(progress_kinds(&client_rx).is_empty());
+    }
+
+    #[test]
+    fn progress_is_dropped_when_creation_times_out() {
+        let (reporter, id, client_rx, server_tx) = pending_reporter(Duration::ZERO);
+        reporter.report("Parsing", 1, 2);
+        assert!(matches!(
+            &*reporter.state.lock().unwrap(),
+            ProgressState::Disabled
+        ));
+
+        // A late response is discarded.
+        server_tx.send(Response::new_ok(id, ()).into()).unwrap();
+        reporter.report("Parsing", 2, 2);
+        drop(reporter);
+        assert!(progress_kinds(&client_rx).is_empty());
+    }
+}
@@ -1,13 +1,21 @@
 use std::sync::Arc;
 
-use crate::{dispatch, lsp_ext, providers::FeatureRequest};
+use crate::{
+    dispatch, lsp_ext,
+    providers::{FeatureRequest, WorkspaceRequest},
+};
 
 use lsp_server::{Request, RequestId};
 use lsp_types::{
     request::{
-        CallHierarchyIncomingCalls, CallHierarchyOutgoingCalls, CallHierarchyPrepare, Completion,
-        DocumentSymbolRequest, GotoDefinition, HoverRequest, References, Rename,
-        ResolveCompletionItem, SemanticTokensFullRequest, SignatureHelpRequest,
+        CallHierarchyIncomingCalls, CallHierarchyOutgoingCalls, CallHierarchyPrepare,
+        CodeActionRequest, Completion, DocumentHighlightRequest, DocumentSymbolRequest,
+        FoldingRangeRequest, Formatting, GotoDeclaration, GotoDefinition, GotoImplementation,
+        GotoTypeDefinition, HoverRequest, InlayHintRequest, PrepareRenameRequest, RangeFormatting,
+        References, Rename, ResolveCompletionItem, SemanticTokensFullDeltaRequest,
+        SemanticTokensFullRequest, SemanticTokensRangeRequest, SignatureHelpRequest,
+        TypeHierarchyPrepare, TypeHierarchySubtypes, TypeHierarchySupertypes, WillRenameFiles,
+        WorkspaceSymbolRequest,
     },
     Url,
 };
@@ -16,15 +24,26 @@ use serde::Serialize;
 use crate::Server;
 
 mod call_hierarchy;
+mod code_action;
 mod completion;
+mod declaration;
 mod definition;
+mod document_highlight;
 mod document_symbol;
+mod folding_range;
+mod formatting;
 mod hover;
+mod implementation;
+mod inlay_hint;
 mod preprocessed_document;
 mod reference;
 mod rename;
 mod semantic_tokens;
 mod signature_help;
+mod type_definition;
+mod type_hierarchy;
+mod will_rename_files;
+mod workspace_symbol;
 
 impl Server {
     pub(super) fn handle_request(&mut self, request: Request) -> anyhow::Result<()> {
@@ -33,11 +52,23 @@ impl Server {
             .on::<ResolveCompletionItem, _>(|id, params| self.resolve_completion_item(id, params))?
             .on::<HoverRequest, _>(|id, params| self.hover(id, params))?
             .on::<GotoDefinition, _>(|id, params| self.definition(id, params))?
-            .on::<SemanticTokensFullRequest, _>(|id, params| self.semantic_tokens(id, params))?
+            .on::<GotoTypeDefinition, _>(|id, params| self.type_definition(id, params))?
+            .on::<GotoImplementation, _>(|id, params| self.implementation(id, params))?
+            .on::<GotoDeclaration, _>(|id, params| self.declaration(id, params))?
+            .on::<SemanticTokensFullDeltaRequest, SemanticTokensFullRequest, SemanticTokensRangeRequest, _>(|id, params| self.semantic_tokens(id, params))?
             .on::<SignatureHelpRequest, _>(|id, params| self.signature_help(id, params))?
             .on::<References, _>(|id, params| self.reference(id, params))?
             .on::<DocumentSymbolRequest, _>(|id, params| self.document_symbol(id, params))?
+            .on::<DocumentHighlightRequest, _>(|id, params| self.document_highlight(id, params))?
+            .on::<PrepareRenameRequest, _>(|id, params| self.prepare_rename(id, params))?
             .on::<Rename, _>(|id, params| self.rename(id, params))?
+            .on::<FoldingRangeRequest, _>(|id, params| self.folding_range(id, params))?
+            .on::<InlayHintRequest, _>(|id, params| self.inlay_hint(id, params))?
+            .on::<CodeActionRequest, _>(|id, params| self.code_action(id, params))?
+            .on::<WorkspaceSymbolRequest, _>(|id, params| self.workspace_symbol(id, params))?
+            .on::<WillRenameFiles, _>(|id, params| self.will_rename_files(id, params))?
+            .on::<Formatting, _>(|id, params| self.formatting(id, params))?
+            .on::<RangeFormatting, _>(|id, params| self.range_formatting(id, params))?
             .on::<CallHierarchyOutgoingCalls, _>(|id, params| {
                 self.call_hierarchy_outgoing(id, params)
             })?
@@ -45,6 +76,11 @@ impl Server {
                 self.call_hierarchy_incoming(id, params)
             })?
             .on::<CallHierarchyPrepare, _>(|id, params| self.call_hierarchy_prepare(id, params))?
+            .on::<TypeHierarchySupertypes, _>(|id, params| {
+                self.type_hierarchy_supertypes(id, params)
+            })?
+            .on::<TypeHierarchySubtypes, _>(|id, params| self.type_hierarchy_subtypes(id, params))?
+            .on::<TypeHierarchyPrepare, _>(|id, params| self.type_hierarchy_prepare(id, params))?
             .on::<lsp_ext::PreprocessedDocument, _>(|id, params| {
                 self.preprocessed_document(id, params)
             })?
@@ -57,6 +93,32 @@ impl Server {
         Ok(())
     }
 
+    /// Handle a request which is not tied to a document, with the items of every document.
+    pub(super) fn handle_workspace_request<P, R, H>(
+        &self,
+        id: RequestId,
+        params: P,
+        handler: H,
+    ) -> anyhow::Result<()>
+    where
+        P: Send + 'static,
+        R: Serialize,
+        H: FnOnce(WorkspaceRequest<P>) -> R + Send + 'static,
+    {
+        self.spawn(move |server| {
+            let request = WorkspaceRequest {
+                params,
+                store: server.store,
+            };
+            let response = lsp_server::Response::new_ok(id, handler(request));
+            if let Err(error) = server.connection.sender.send(response.into()) {
+                log::error!("Failed to send response: {}", error);
+            }
+        });
+
+        Ok(())
+    }
+
     pub(super) fn handle_feature_request<P, R, H>(
         &self,
         id: RequestId,
@@ -68,31 +130,44 @@ impl Server {
         P: Send + 'static,
         R: Serialize,
         H: FnOnce(FeatureRequest<P>) -> R + Send + 'static,
+    {
+        self.handle_fallible_feature_request(id, params, uri, move |request| Ok(handler(request)))
+    }
+
+    /// Handle a feature request whose handler can reject the request, in which case the error
+    /// message is sent back to the client.
+    pub(super) fn handle_fallible_feature_request<P, R, H>(
+        &self,
+        id: RequestId,
+        params: P,
+        uri: Arc<Url>,
+        handler: H,
+    ) -> anyhow::Result<()>
+    where
+        P: Send + 'static,
+        R: Serialize,
+        H: FnOnce(FeatureRequest<P>) -> Result<R, String> + Send + 'static,
     {
         self.spawn(move |server| {
             let request = server.feature_request(uri, params);
-            if request.store.iter().next().is_none() {
+            let response = if request.store.iter().next().is_none() {
                 let code = lsp_server::ErrorCode::InvalidRequest as i32;
                 let message = "unknown document".to_string();
-                let response = lsp_server::Response::new_err(id, code, message);
-                match server.connection.sender.send(response.into()) {
-                    Ok(_) => {}
-                    Err(error) => {
-                        log::error!("Failed to send response: {}", error);
-                    }
-                }
+                lsp_server::Response::new_err(id, code, message)
             } else {
-                let result = handler(request);
-                match server
-                    .connection
-                    .sender
-                    .send(lsp_server::Response::new_ok(id, result).into())
-                {
-                    Ok(_) => {}
-                    Err(error) => {
-                        log::error!("Failed to send response: {}", error);
+                match handler(request) {
+                    Ok(result) => lsp_server::Response::new_ok(id, result),
+                    Err(message) => {
+                        let code = lsp_server::ErrorCode::InvalidParams as i32;
+                        lsp_server::Response::new_err(id, code, message)
                     }
                 }
+            };
+            match server.connection.sender.send(response.into()) {
+                Ok(_) => {}
+                Err(error) => {
+                    log::error!("Failed to send response: {}", error);
+                }
             }
         });
 
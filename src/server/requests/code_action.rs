@@ -0,0 +1,23 @@
+use crate::utils;
+use std::sync::Arc;
+
+use lsp_server::RequestId;
+use lsp_types::CodeActionParams;
+
+use crate::{providers, Server};
+
+impl Server {
+    pub(super) fn code_action(
+        &mut self,
+        id: RequestId,
+        mut params: CodeActionParams,
+    ) -> anyhow::Result<()> {
+        utils::normalize_uri(&mut params.text_document.uri);
+        let uri = Arc::new(params.text_document.uri.clone());
+        let _ = self.read_unscanned_document(uri.clone());
+
+        self.handle_feature_request(id, params, uri, providers::code_action::provide_code_action)?;
+
+        Ok(())
+    }
+}
@@ -0,0 +1,29 @@
+use crate::utils;
+use std::sync::Arc;
+
+use lsp_server::RequestId;
+use lsp_types::request::GotoDeclarationParams;
+
+use crate::{providers, Server};
+
+impl Server {
+    pub(super) fn declaration(
+        &mut self,
+        id: RequestId,
+        mut params: GotoDeclarationParams,
+    ) -> anyhow::Result<()> {
+        utils::normalize_uri(&mut params.text_document_position_params.text_document.uri);
+        let uri = Arc::new(
+            params
+                .text_document_position_params
+                .text_document
+                .uri
+                .clone(),
+        );
+        let _ = self.read_unscanned_document(uri.clone());
+
+        self.handle_feature_request(id, params, uri, providers::declaration::provide_declaration)?;
+
+        Ok(())
+    }
+}
@@ -0,0 +1,28 @@
+use crate::utils;
+use std::sync::Arc;
+
+use lsp_server::RequestId;
+use lsp_types::FoldingRangeParams;
+
+use crate::{providers, Server};
+
+impl Server {
+    pub(super) fn folding_range(
+        &mut self,
+        id: RequestId,
+        mut params: FoldingRangeParams,
+    ) -> anyhow::Result<()> {
+        utils::normalize_uri(&mut params.text_document.uri);
+        let uri = Arc::new(params.text_document.uri.clone());
+        let _ = self.read_unscanned_document(uri.clone());
+
+        self.handle_feature_request(
+            id,
+            params,
+            uri,
+            providers::folding_range::provide_folding_range,
+        )?;
+
+        Ok(())
+    }
+}
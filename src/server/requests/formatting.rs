@@ -0,0 +1,42 @@
+use crate::utils;
+use std::sync::Arc;
+
+use lsp_server::RequestId;
+use lsp_types::{DocumentFormattingParams, DocumentRangeFormattingParams};
+
+use crate::{providers, Server};
+
+impl Server {
+    pub(super) fn formatting(
+        &mut self,
+        id: RequestId,
+        mut params: DocumentFormattingParams,
+    ) -> anyhow::Result<()> {
+        utils::normalize_uri(&mut params.text_document.uri);
+        let uri = Arc::new(params.text_document.uri.clone());
+        let _ = self.read_unscanned_document(uri.clone());
+
+        self.handle_feature_request(id, params, uri, providers::formatting::provide_formatting)?;
+
+        Ok(())
+    }
+
+    pub(super) fn range_formatting(
+        &mut self,
+        id: RequestId,
+        mut params: DocumentRangeFormattingParams,
+    ) -> anyhow::Result<()> {
+        utils::normalize_uri(&mut params.text_document.uri);
+        let uri = Arc::new(params.text_document.uri.clone());
+        let _ = self.read_unscanned_document(uri.clone());
+
+        self.handle_feature_request(
+            id,
+            params,
+            uri,
+            providers::formatting::provide_range_formatting,
+        )?;
+
+        Ok(())
+    }
+}
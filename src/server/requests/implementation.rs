@@ -0,0 +1,34 @@
+use crate::utils;
+use std::sync::Arc;
+
+use lsp_server::RequestId;
+use lsp_types::request::GotoImplementationParams;
+
+use crate::{providers, Server};
+
+impl Server {
+    pub(super) fn implementation(
+        &mut self,
+        id: RequestId,
+        mut params: GotoImplementationParams,
+    ) -> anyhow::Result<()> {
+        utils::normalize_uri(&mut params.text_document_position_params.text_document.uri);
+        let uri = Arc::new(
+            params
+                .text_document_position_params
+                .text_document
+                .uri
+                .clone(),
+        );
+        let _ = self.read_unscanned_document(uri.clone());
+
+        self.handle_feature_request(
+            id,
+            params,
+            uri,
+            providers::implementation::provide_implementation,
+        )?;
+
+        Ok(())
+    }
+}
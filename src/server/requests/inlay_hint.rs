@@ -0,0 +1,23 @@
+use crate::utils;
+use std::sync::Arc;
+
+use lsp_server::RequestId;
+use lsp_types::InlayHintParams;
+
+use crate::{providers, Server};
+
+impl Server {
+    pub(super) fn inlay_hint(
+        &mut self,
+        id: RequestId,
+        mut params: InlayHintParams,
+    ) -> anyhow::Result<()> {
+        utils::normalize_uri(&mut params.text_document.uri);
+        let uri = Arc::new(params.text_document.uri.clone());
+        let _ = self.read_unscanned_document(uri.clone());
+
+        self.handle_feature_request(id, params, uri, providers::inlay_hint::provide_inlay_hints)?;
+
+        Ok(())
+    }
+}
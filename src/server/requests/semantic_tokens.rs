@@ -2,7 +2,7 @@ use crate::utils;
 use std::sync::Arc;
 
 use lsp_server::RequestId;
-use lsp_types::SemanticTokensParams;
+use lsp_types::{SemanticTokensDeltaParams, SemanticTokensParams, SemanticTokensRangeParams};
 
 use crate::{providers, Server};
 
@@ -25,4 +25,42 @@ impl Server {
 
         Ok(())
     }
+
+    pub(super) fn semantic_tokens_delta(
+        &mut self,
+        id: RequestId,
+        mut params: SemanticTokensDeltaParams,
+    ) -> anyhow::Result<()> {
+        utils::normalize_uri(&mut params.text_document.uri);
+        let uri = Arc::new(params.text_document.uri.clone());
+        let _ = self.read_unscanned_document(uri.clone());
+
+        self.handle_feature_request(
+            id,
+            params,
+            uri,
+            providers::semantic_tokens::provide_semantic_tokens_delta,
+        )?;
+
+        Ok(())
+    }
+
+    pub(super) fn semantic_tokens_range(
+        &mut self,
+        id: RequestId,
+        mut params: SemanticTokensRangeParams,
+    ) -> anyhow::Result<()> {
+        utils::normalize_uri(&mut params.text_document.uri);
+        let uri = Arc::new(params.text_document.uri.clone());
+        let _ = self.read_unscanned_document(uri.clone());
+
+        self.handle_feature_request(
+            id,
+            params,
+            uri,
+            providers::semantic_tokens::provide_semantic_tokens_range,
+        )?;
+
+        Ok(())
+    }
 }
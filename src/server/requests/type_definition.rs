@@ -0,0 +1,34 @@
+use crate::utils;
+use std::sync::Arc;
+
+use lsp_server::RequestId;
+use lsp_types::request::GotoTypeDefinitionParams;
+
+use crate::{providers, Server};
+
+impl Server {
+    pub(super) fn type_definition(
+        &mut self,
+        id: RequestId,
+        mut params: GotoTypeDefinitionParams,
+    ) -> anyhow::Result<()> {
+        utils::normalize_uri(&mut params.text_document_position_params.text_document.uri);
+        let uri = Arc::new(
+            params
+                .text_document_position_params
+                .text_document
+                .uri
+                .clone(),
+        );
+        let _ = self.read_unscanned_document(uri.clone());
+
+        self.handle_feature_request(
+            id,
+            params,
+            uri,
+            providers::type_definition::provide_type_definition,
+        )?;
+
+        Ok(())
+    }
+}
@@ -0,0 +1,58 @@
+use crate::utils;
+use std::sync::Arc;
+
+use lsp_server::RequestId;
+use lsp_types::{
+    TypeHierarchyPrepareParams, TypeHierarchySubtypesParams, TypeHierarchySupertypesParams,
+};
+
+use crate::{providers, Server};
+
+impl Server {
+    pub(super) fn type_hierarchy_prepare(
+        &mut self,
+        id: RequestId,
+        mut params: TypeHierarchyPrepareParams,
+    ) -> anyhow::Result<()> {
+        utils::normalize_uri(&mut params.text_document_position_params.text_document.uri);
+        let uri = Arc::new(
+            params
+                .text_document_position_params
+                .text_document
+                .uri
+                .clone(),
+        );
+
+        let _ = self.read_unscanned_document(uri.clone());
+
+        self.handle_feature_request(id, params, uri, providers::type_hierarchy::prepare)?;
+
+        Ok(())
+    }
+
+    pub(super) fn type_hierarchy_supertypes(
+        &mut self,
+        id: RequestId,
+        mut params: TypeHierarchySupertypesParams,
+    ) -> anyhow::Result<()> {
+        utils::normalize_uri(&mut params.item.uri);
+        let uri = Arc::new(params.item.uri.clone());
+
+        self.handle_feature_request(id, params, uri, providers::type_hierarchy::supertypes)?;
+
+        Ok(())
+    }
+
+    pub(super) fn type_hierarchy_subtypes(
+        &mut self,
+        id: RequestId,
+        mut params: TypeHierarchySubtypesParams,
+    ) -> anyhow::Result<()> {
+        utils::normalize_uri(&mut params.item.uri);
+        let uri = Arc::new(params.item.uri.clone());
+
+        self.handle_feature_request(id, params, uri, providers::type_hierarchy::subtypes)?;
+
+        Ok(())
+    }
+}
@@ -0,0 +1,20 @@
+use lsp_server::RequestId;
+use lsp_types::RenameFilesParams;
+
+use crate::{providers, Server};
+
+impl Server {
+    pub(super) fn will_rename_files(
+        &mut self,
+        id: RequestId,
+        params: RenameFilesParams,
+    ) -> anyhow::Result<()> {
+        self.handle_workspace_request(
+            id,
+            params,
+            providers::file_rename::provide_will_rename_files,
+        )?;
+
+        Ok(())
+    }
+}
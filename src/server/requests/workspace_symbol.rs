@@ -0,0 +1,20 @@
+use lsp_server::RequestId;
+use lsp_types::WorkspaceSymbolParams;
+
+use crate::{providers, Server};
+
+impl Server {
+    pub(super) fn workspace_symbol(
+        &mut self,
+        id: RequestId,
+        params: WorkspaceSymbolParams,
+    ) -> anyhow::Result<()> {
+        self.handle_workspace_request(
+            id,
+            params,
+            providers::workspace_symbol::provide_workspace_symbol,
+        )?;
+
+        Ok(())
+    }
+}
@@ -1333,4 +1333,98 @@ void foo(){
             output
         );
     }
+
+    #[test]
+    fn conditional_blocks() {
+        let input = r#"#if 1
+int foo;
+int bar;
+#else
+int baz;
+#endif
+#if 0
+#endif"#;
+        let mut preprocessor = SourcepawnPreprocessor::new(
+            Arc::new(Url::parse("https://example.net").unwrap()),
+            input,
+        );
+        preprocessor.preprocess_input(&mut extend_macros).unwrap();
+        let blocks: Vec<(u32, u32)> = preprocessor
+            .conditional_blocks
+            .iter()
+            .map(|range| (range.start.line, range.end.line))
+            .collect();
+        assert_eq!(blocks, vec![(0, 2), (3, 4)]);
+    }
+
+    #[test]
+    fn inactive_ranges() {
+        let input = r#"#if 0
+int foo;
+int bar;
+#endif
+int baz;"#;
+        let mut preprocessor = SourcepawnPreprocessor::new(
+            Arc::new(Url::parse("https://example.net").unwrap()),
+            input,
+        );
+        preprocessor.preprocess_input(&mut extend_macros).unwrap();
+        let ranges: Vec<(u32, u32)> = preprocessor
+            .inactive_ranges()
+            .iter()
+            .map(|range| (range.start.line, range.end.line))
+            .collect();
+        assert_eq!(ranges.len(), 1);
+        assert!(ranges[0].0 <= 1 && ranges[0].1 >= 2 && ranges[0].1 < 4);
+    }
+
+    #[test]
+    fn predefined_macro() {
+        let input = r#"#if defined DEBUG
+int foo;
+#else
+int bar;
+#endif
+int baz = VERSION;"#;
+        let output = r#"
+int foo;
+
+
+
+int baz = 12;"#;
+        let mut preprocessor = SourcepawnPreprocessor::new(
+            Arc::new(Url::parse("https://example.net").unwrap()),
+            input,
+        );
+        preprocessor.define_macro("DEBUG", "");
+        preprocessor.define_macro("VERSION", "12");
+        assert_eq!(
+            preprocessor.preprocess_input(&mut extend_macros).unwrap(),
+            output
+        );
+    }
+
+    #[test]
+    fn macro_body_text_round_trip() {
+        let input = r#"#define FOO(%1,%2) %1 + %2 * 2
+int foo = FOO(1, 3);"#;
+        let mut preprocessor = SourcepawnPreprocessor::new(
+            Arc::new(Url::parse("https://example.net").unwrap()),
+            input,
+        );
+        let output = preprocessor.preprocess_input(&mut extend_macros).unwrap();
+        let macro_ = preprocessor.macros.get("FOO").unwrap();
+        let restored = Macro::from_text(macro_.params().cloned(), &macro_.body_text());
+        assert_eq!(restored.body_text(), macro_.body_text());
+
+        let mut preprocessor = SourcepawnPreprocessor::new(
+            Arc::new(Url::parse("https://example.net").unwrap()),
+            "int foo = FOO(1, 3);",
+        );
+        preprocessor.macros.insert("FOO".to_string(), restored);
+        assert_eq!(
+            preprocessor.preprocess_input(&mut extend_macros).unwrap(),
+            output.lines().last().unwrap()
+        );
+    }
 }
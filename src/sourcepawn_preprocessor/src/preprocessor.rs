@@ -43,6 +43,10 @@ pub struct SourcepawnPreprocessor<'a> {
     current_line: String,
     prev_end: u32,
     conditions_stack: Vec<ConditionState>,
+    conditional_blocks_stack: Vec<u32>,
+    /// Line ranges of the branches of the `#if` directives, excluding the closing `#else`,
+    /// `#elseif` or `#endif` line.
+    pub conditional_blocks: Vec<lsp_types::Range>,
     out: Vec<String>,
     pub offsets: FxHashMap<u32, Vec<Offset>>,
 }
@@ -54,6 +58,50 @@ pub struct Macro {
     pub(crate) body: Vec<Symbol>,
 }
 
+impl Macro {
+    /// Build a macro by lexing the text of its body.
+    ///
+    /// # Arguments
+    ///
+    /// * `params` - Indexes of the parameters of the macro, if it is a function-like macro.
+    /// * `body` - Text of the body of the macro.
+    pub fn from_text(params: Option<Vec<i8>>, body: &str) -> Self {
+        let mut macro_ = Macro {
+            nb_params: params.as_ref().map_or(0, |params| {
+                params.iter().filter(|&n| *n != -1).count() as i8
+            }),
+            params,
+            body: vec![],
+        };
+        let mut lexer = SourcepawnLexer::new(body);
+        while let Some(symbol) = lexer.next() {
+            if matches!(symbol.token_kind, TokenKind::Newline | TokenKind::Eof) {
+                continue;
+            }
+            macro_.body.push(symbol);
+        }
+
+        macro_
+    }
+
+    /// Return the indexes of the parameters of the macro, if it is a function-like macro.
+    pub fn params(&self) -> Option<&Vec<i8>> {
+        self.params.as_ref()
+    }
+
+    /// Return the text of the body of the macro, with the whitespaces between its symbols, so
+    /// that it can be lexed again with [Macro::from_text].
+    pub fn body_text(&self) -> String {
+        let mut text = String::new();
+        for symbol in self.body.iter() {
+            text.push_str(&" ".repeat(symbol.delta.col.unsigned_abs() as usize));
+            text.push_str(&symbol.text());
+        }
+
+        text
+    }
+}
+
 impl<'a> SourcepawnPreprocessor<'a> {
     pub fn new(document_uri: Arc<Url>, input: &'a str) -> Self {
         Self {
@@ -68,6 +116,8 @@ impl<'a> SourcepawnPreprocessor<'a> {
             evaluated_define_symbols: vec![],
             prev_end: 0,
             conditions_stack: vec![],
+            conditional_blocks_stack: vec![],
+            conditional_blocks: vec![],
             out: vec![],
             macros: FxHashMap::default(),
             expansion_stack: vec![],
@@ -75,14 +125,27 @@ impl<'a> SourcepawnPreprocessor<'a> {
         }
     }
 
+    /// Define an object-like macro as if it was declared with `#define name value` before the
+    /// start of the input.
+    ///
+    /// # Arguments
+    ///
+    /// * `name` - Name of the macro.
+    /// * `value` - Body of the macro, can be empty.
+    pub fn define_macro(&mut self, name: &str, value: &str) {
+        self.macros
+            .insert(name.to_string(), Macro::from_text(None, value));
+    }
+
     pub fn add_diagnostics(&self, diagnostics: &mut Vec<Diagnostic>) {
-        self.get_disabled_diagnostics(diagnostics);
         self.get_macro_not_found_diagnostics(diagnostics);
         self.get_evaluation_error_diagnostics(diagnostics);
         self.get_include_not_found_diagnostics(diagnostics);
     }
 
-    fn get_disabled_diagnostics(&self, diagnostics: &mut Vec<Diagnostic>) {
+    /// Return the ranges of the lines skipped because of an inactive preprocessor condition.
+    /// Consecutive lines are merged in a single range.
+    pub fn inactive_ranges(&self) -> Vec<lsp_types::Range> {
         let mut ranges: Vec<lsp_types::Range> = vec![];
         for range in self.skipped_lines.iter() {
             if let Some(old_range) = ranges.pop() {
@@ -100,13 +163,8 @@ impl<'a> SourcepawnPreprocessor<'a> {
         for range in ranges.iter_mut() {
             range.start.character = 0;
         }
-        diagnostics.extend(ranges.iter().map(|range| Diagnostic {
-            range: *range,
-            message: "Code disabled by the preprocessor.".to_string(),
-            severity: Some(lsp_types::DiagnosticSeverity::HINT),
-            tags: Some(vec![lsp_types::DiagnosticTag::UNNECESSARY]),
-            ..Default::default()
-        }));
+
+        ranges
     }
 
     fn get_macro_not_found_diagnostics(&self, diagnostics: &mut Vec<Diagnostic>) {
@@ -275,6 +333,7 @@ impl<'a> SourcepawnPreprocessor<'a> {
     }
 
     fn process_else_directive(&mut self, symbol: &Symbol) -> anyhow::Result<()> {
+        self.split_conditional_block(symbol);
         let last = self
             .conditions_stack
             .pop()
@@ -293,6 +352,7 @@ impl<'a> SourcepawnPreprocessor<'a> {
     }
 
     fn process_endif_directive(&mut self, symbol: &Symbol) -> anyhow::Result<()> {
+        self.close_conditional_block(symbol);
         self.conditions_stack
             .pop()
             .context("Expect if before endif clause")?;
@@ -309,6 +369,26 @@ impl<'a> SourcepawnPreprocessor<'a> {
         Ok(())
     }
 
+    fn open_conditional_block(&mut self, symbol: &Symbol) {
+        self.conditional_blocks_stack.push(symbol.range.start.line);
+    }
+
+    fn split_conditional_block(&mut self, symbol: &Symbol) {
+        self.close_conditional_block(symbol);
+        self.open_conditional_block(symbol);
+    }
+
+    fn close_conditional_block(&mut self, symbol: &Symbol) {
+        if let Some(start_line) = self.conditional_blocks_stack.pop() {
+            if symbol.range.start.line > start_line + 1 {
+                self.conditional_blocks.push(lsp_types::Range::new(
+                    Position::new(start_line, 0),
+                    Position::new(symbol.range.start.line - 1, 0),
+                ));
+            }
+        }
+    }
+
     fn process_directive<F>(
         &mut self,
         include_file: &mut F,
@@ -319,8 +399,12 @@ impl<'a> SourcepawnPreprocessor<'a> {
         F: FnMut(&mut FxHashMap<String, Macro>, String, &Url, bool) -> anyhow::Result<()>,
     {
         match dir {
-            PreprocDir::MIf => self.process_if_directive(symbol),
+            PreprocDir::MIf => {
+                self.open_conditional_block(symbol);
+                self.process_if_directive(symbol)
+            }
             PreprocDir::MElseif => {
+                self.split_conditional_block(symbol);
                 let last = self
                     .conditions_stack
                     .pop()
@@ -519,11 +603,13 @@ impl<'a> SourcepawnPreprocessor<'a> {
             TokenKind::PreprocDir(dir) => match dir {
                 PreprocDir::MIf => {
                     // Keep track of any nested if statements to ensure we properly pop when reaching an endif.
+                    self.open_conditional_block(symbol);
                     self.conditions_stack.push(ConditionState::Activated);
                 }
                 PreprocDir::MEndif => self.process_endif_directive(symbol)?,
                 PreprocDir::MElse => self.process_else_directive(symbol)?,
                 PreprocDir::MElseif => {
+                    self.split_conditional_block(symbol);
                     let last = self
                         .conditions_stack
                         .pop()
@@ -5,12 +5,13 @@ use lsp_types::{
     CompletionParams, DocumentSymbol, GotoDefinitionParams, Hover, HoverContents, HoverParams,
     LanguageString, LocationLink, MarkedString, Range, SymbolKind, SymbolTag, Url,
 };
+use serde::{Deserialize, Serialize};
 
 use crate::{providers::hover::description::Description, utils::uri_to_file_name};
 
 use super::Location;
 
-#[derive(Debug, Clone)]
+#[derive(Debug, Clone, Serialize, Deserialize)]
 /// SPItem representation of a SourcePawn define.
 pub struct DefineItem {
     /// Name of the define.
@@ -38,6 +39,7 @@ pub struct DefineItem {
     pub uri: Arc<Url>,
 
     /// References to this define.
+    #[serde(skip)]
     pub references: Vec<Location>,
 }
 
@@ -5,11 +5,12 @@ use lsp_types::{
     DocumentSymbol, GotoDefinitionParams, Hover, HoverContents, HoverParams, LanguageString,
     LocationLink, MarkedString, Range, SymbolKind, SymbolTag, Url,
 };
+use serde::{Deserialize, Serialize};
 
 use super::{Location, SPItem};
 use crate::{providers::hover::description::Description, utils::uri_to_file_name};
 
-#[derive(Debug, Clone)]
+#[derive(Debug, Clone, Serialize, Deserialize)]
 /// SPItem representation of a SourcePawn enum.
 pub struct EnumItem {
     /// Name of the enum.
@@ -34,6 +35,7 @@ pub struct EnumItem {
     pub uri: Arc<Url>,
 
     /// References to this enum.
+    #[serde(skip)]
     pub references: Vec<Location>,
 
     /// Children ([EnumMemberItem](super::enum_member_item::EnumMemberItem)) of this enum.
@@ -6,13 +6,14 @@ use lsp_types::{
     MarkedString, Range, SymbolKind, SymbolTag, Url,
 };
 use lsp_types::{GotoDefinitionParams, LocationLink};
+use serde::{Deserialize, Serialize};
 
 use crate::providers::hover::description::Description;
 
 use super::Location;
 use super::SPItem;
 
-#[derive(Debug, Clone)]
+#[derive(Debug, Clone, Serialize, Deserialize)]
 /// SPItem representation of a SourcePawn enum member.
 pub struct EnumMemberItem {
     /// Name of the enum member.
@@ -25,6 +26,7 @@ pub struct EnumMemberItem {
     pub v_range: Range,
 
     /// Parent of the method. None if it's a first class function.
+    #[serde(skip)]
     pub parent: Weak<RwLock<SPItem>>,
 
     /// Description of the enum member.
@@ -34,6 +36,7 @@ pub struct EnumMemberItem {
     pub uri: Arc<Url>,
 
     /// References to this enum.
+    #[serde(skip)]
     pub references: Vec<Location>,
 }
 
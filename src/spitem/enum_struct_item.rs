@@ -6,10 +6,11 @@ use lsp_types::{
     DocumentSymbol, GotoDefinitionParams, Hover, HoverContents, HoverParams, LanguageString,
     LocationLink, MarkedString, Range, SymbolKind, SymbolTag, Url,
 };
+use serde::{Deserialize, Serialize};
 
 use crate::{providers::hover::description::Description, utils::uri_to_file_name};
 
-#[derive(Debug, Clone)]
+#[derive(Debug, Clone, Serialize, Deserialize)]
 /// SPItem representation of a SourcePawn enum struct.
 pub struct EnumStructItem {
     /// Name of the enum struct.
@@ -34,6 +35,7 @@ pub struct EnumStructItem {
     pub uri: Arc<Url>,
 
     /// References to this enum struct.
+    #[serde(skip)]
     pub references: Vec<Location>,
 
     /// Children ([FunctionItem](super::function_item::FunctionItem),
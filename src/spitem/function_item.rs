@@ -10,13 +10,14 @@ use lsp_types::{
     LanguageString, LocationLink, MarkedString, MarkupContent, ParameterInformation, Position,
     Range, SignatureInformation, SymbolKind, SymbolTag, TextEdit, Url,
 };
+use serde::{Deserialize, Serialize};
 
 use crate::providers::hover::description::Description;
 use crate::utils::uri_to_file_name;
 
 use super::SPItem;
 
-#[derive(Debug, Clone)]
+#[derive(Debug, Clone, Serialize, Deserialize)]
 /// SPItem representation of a first order SourcePawn function, which can be converted to a
 /// [CompletionItem](lsp_types::CompletionItem), [Location](lsp_types::Location), etc.
 pub struct FunctionItem {
@@ -54,12 +55,19 @@ pub struct FunctionItem {
     pub definition_type: FunctionDefinitionType,
 
     /// References to this function.
+    #[serde(skip)]
     pub references: Vec<Location>,
 
     /// Parameters of the function.
     pub params: Vec<Arc<RwLock<Parameter>>>,
 
+    /// Whether the function accepts a variable number of arguments after its parameters, e.g
+    /// `any ...`.
+    #[serde(default)]
+    pub is_variadic: bool,
+
     /// Parent of the method. None if it's a first class function.
+    #[serde(skip)]
     pub parent: Option<Weak<RwLock<SPItem>>>,
 
     /// Children ([VariableItem](super::variable_item::VariableItem)) of this function.
@@ -75,6 +83,10 @@ impl FunctionItem {
         self.visibility.contains(&FunctionVisibility::Static)
     }
 
+    pub fn is_public(&self) -> bool {
+        self.visibility.contains(&FunctionVisibility::Public)
+    }
+
     /// Return a vector of [CompletionItem](lsp_types::CompletionItem) from a [FunctionItem] and its children.
     ///
     /// If the conditions are not appropriate (ex: asking for a static outside of its file), return None.
@@ -427,7 +439,7 @@ impl FunctionItem {
 }
 
 /// Visibility of a SourcePawn function.
-#[derive(Debug, PartialEq, Eq, Clone, Hash)]
+#[derive(Debug, PartialEq, Eq, Clone, Hash, Serialize, Deserialize)]
 pub enum FunctionVisibility {
     Public,
     Static,
@@ -435,7 +447,7 @@ pub enum FunctionVisibility {
 }
 
 /// Definition type of a SourcePawn function.
-#[derive(Debug, PartialEq, Eq, Clone, Default)]
+#[derive(Debug, PartialEq, Eq, Clone, Default, Serialize, Deserialize)]
 pub enum FunctionDefinitionType {
     Forward,
     Native,
@@ -4,8 +4,9 @@ use lsp_types::{
     CompletionItem, CompletionParams, GotoDefinitionParams, Hover, HoverContents, HoverParams,
     LocationLink, MarkedString, Position, Range, Url,
 };
+use serde::{Deserialize, Serialize};
 
-#[derive(Debug, Clone)]
+#[derive(Debug, Clone, Serialize, Deserialize)]
 /// SPItem representation of a SourcePawn include.
 pub struct IncludeItem {
     /// Name of the include.
@@ -4,23 +4,26 @@ use super::Location;
 use lsp_types::{
     CompletionItem, CompletionItemKind, CompletionItemLabelDetails, CompletionParams,
     DocumentSymbol, GotoDefinitionParams, Hover, HoverContents, HoverParams, LanguageString,
-    LocationLink, MarkedString, Range, SymbolKind, SymbolTag, Url,
+    LocationLink, MarkedString, Range, SymbolKind, SymbolTag, TypeHierarchyItem, Url,
 };
+use serde::{Deserialize, Serialize};
 
 use crate::{providers::hover::description::Description, utils::uri_to_file_name};
 
 use super::SPItem;
 
-#[derive(Debug, Clone)]
+#[derive(Debug, Clone, Serialize, Deserialize)]
 /// SPItem representation of a SourcePawn methodmap.
 pub struct MethodmapItem {
     /// Name of the methodmap.
     pub name: String,
 
     /// Parent of the methodmap.
+    #[serde(skip)]
     pub parent: Option<Arc<RwLock<SPItem>>>,
 
-    /// Temporary parent of the methodmap.
+    /// Name of the parent of the methodmap, used to resolve [parent](Self::parent) again each time
+    /// the references are resolved.
     pub tmp_parent: Option<String>,
 
     /// Range of the name of the methodmap.
@@ -42,6 +45,7 @@ pub struct MethodmapItem {
     pub uri: Arc<Url>,
 
     /// References to this methodmap.
+    #[serde(skip)]
     pub references: Vec<Location>,
 
     /// Children ([FunctionItem](super::function_item::FunctionItem),
@@ -50,6 +54,11 @@ pub struct MethodmapItem {
 }
 
 impl MethodmapItem {
+    /// Return the name of the parent of the methodmap, whether it is resolved or not.
+    pub(crate) fn parent_name(&self) -> Option<String> {
+        self.tmp_parent.clone()
+    }
+
     /// Return a vector of [CompletionItem](lsp_types::CompletionItem) from a [MethodmapItem] and its children.
     ///
     /// # Arguments
@@ -141,6 +150,24 @@ impl MethodmapItem {
         })
     }
 
+    /// Return a [TypeHierarchyItem] from a [MethodmapItem].
+    pub(crate) fn to_type_hierarchy(&self) -> TypeHierarchyItem {
+        TypeHierarchyItem {
+            name: self.name.clone(),
+            kind: SymbolKind::CLASS,
+            tags: if self.description.deprecated.is_some() {
+                Some(SymbolTag::DEPRECATED)
+            } else {
+                None
+            },
+            detail: uri_to_file_name(&self.uri),
+            uri: (*self.uri).clone(),
+            range: self.v_full_range,
+            selection_range: self.v_range,
+            data: None,
+        }
+    }
+
     /// Return a key to be used as a unique identifier in a map containing all the items.
     pub(crate) fn key(&self) -> String {
         self.name.clone()
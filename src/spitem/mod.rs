@@ -9,12 +9,13 @@ use lsp_types::{
     GotoDefinitionParams, Hover, HoverParams, LocationLink, MarkupContent, Position, Range,
     SignatureInformation, Url,
 };
+use serde::{Deserialize, Serialize};
 
 use crate::{
     document::Document,
     providers::hover::description::Description,
     store::Store,
-    utils::{range_contains_pos, range_equals_range},
+    utils::{range_contains_pos, range_equals_range, shift_range_lines},
 };
 
 use self::parameter::Parameter;
@@ -54,7 +55,7 @@ impl Location {
     }
 }
 
-#[derive(Debug, Clone)]
+#[derive(Debug, Clone, Serialize, Deserialize)]
 /// Generic representation of an item, which can be converted to a
 /// [CompletionItem](lsp_types::CompletionItem), [Location](lsp_types::Location), etc.
 pub enum SPItem {
@@ -73,10 +74,25 @@ pub enum SPItem {
 
 impl Store {
     pub fn get_all_items(&self, flat: bool) -> (Vec<Arc<RwLock<SPItem>>>, Duration) {
+        self.get_all_items_of_main(self.main_uri(), flat)
+    }
+
+    /// Return the items of the include closure of a main path, or of all the documents if there
+    /// is no main path.
+    ///
+    /// # Arguments
+    ///
+    /// * `main_uri` - [Uri](Url) of the main path.
+    /// * `flat` - Whether to include the children of the items.
+    pub(crate) fn get_all_items_of_main(
+        &self,
+        main_uri: Option<Url>,
+        flat: bool,
+    ) -> (Vec<Arc<RwLock<SPItem>>>, Duration) {
         log::debug!("Getting all items from store. flat: {}", flat);
         let mut include_duration = Instant::now().elapsed();
         let mut all_items = vec![];
-        if let Ok(Some(main_path_uri)) = self.environment.options.get_main_path_uri() {
+        if let Some(main_path_uri) = main_uri {
             let mut includes = FxHashSet::default();
             includes.insert(main_path_uri.clone());
             if let Some(document) = self.documents.get(&main_path_uri) {
@@ -109,6 +125,39 @@ impl Store {
         (all_items, include_duration)
     }
 
+    /// Return the main path used to get the items of the [Store].
+    pub(crate) fn main_uri(&self) -> Option<Url> {
+        self.scoped_main_uri
+            .clone()
+            .or_else(|| self.main_uris.first().cloned())
+            .or_else(|| self.environment.options.get_main_path_uri().ok().flatten())
+    }
+
+    /// Return the main path which includes a document, or the default main path if none of them
+    /// includes it.
+    ///
+    /// # Arguments
+    ///
+    /// * `uri` - [Uri](Url) of the document.
+    pub(crate) fn main_uri_of(&self, uri: &Url) -> Option<Url> {
+        if self.main_uris.len() > 1 {
+            for main_uri in self.main_uris.iter() {
+                if main_uri == uri {
+                    return Some(main_uri.clone());
+                }
+                if let Some(document) = self.documents.get(main_uri) {
+                    let mut includes = FxHashSet::default();
+                    self.get_included_files(document, &mut includes);
+                    if includes.contains(uri) {
+                        return Some(main_uri.clone());
+                    }
+                }
+            }
+        }
+
+        self.main_uri()
+    }
+
     pub(crate) fn get_included_files(&self, document: &Document, includes: &mut FxHashSet<Url>) {
         for include_uri in document.includes.keys() {
             if includes.contains(include_uri) {
@@ -387,6 +436,127 @@ impl SPItem {
         }
     }
 
+    /// Move the ranges of the item by a number of lines, when lines are inserted or deleted
+    /// above it. The children and the references are left untouched.
+    ///
+    /// # Arguments
+    ///
+    /// * `delta` - Number of lines to move the item by, negative to move it up.
+    fn shift_lines(&mut self, delta: i32) {
+        let ranges = match self {
+            SPItem::Variable(item) => vec![&mut item.range, &mut item.v_range],
+            SPItem::Function(item) => vec![
+                &mut item.range,
+                &mut item.v_range,
+                &mut item.full_range,
+                &mut item.v_full_range,
+            ],
+            SPItem::Enum(item) => vec![
+                &mut item.range,
+                &mut item.v_range,
+                &mut item.full_range,
+                &mut item.v_full_range,
+            ],
+            SPItem::EnumMember(item) => vec![&mut item.range, &mut item.v_range],
+            SPItem::EnumStruct(item) => vec![
+                &mut item.range,
+                &mut item.v_range,
+                &mut item.full_range,
+                &mut item.v_full_range,
+            ],
+            SPItem::Define(item) => vec![
+                &mut item.range,
+                &mut item.v_range,
+                &mut item.full_range,
+                &mut item.v_full_range,
+            ],
+            SPItem::Methodmap(item) => vec![
+                &mut item.range,
+                &mut item.v_range,
+                &mut item.full_range,
+                &mut item.v_full_range,
+            ],
+            SPItem::Property(item) => vec![
+                &mut item.range,
+                &mut item.v_range,
+                &mut item.full_range,
+                &mut item.v_full_range,
+            ],
+            SPItem::Typedef(item) => vec![
+                &mut item.range,
+                &mut item.v_range,
+                &mut item.full_range,
+                &mut item.v_full_range,
+            ],
+            SPItem::Typeset(item) => vec![
+                &mut item.range,
+                &mut item.v_range,
+                &mut item.full_range,
+                &mut item.v_full_range,
+            ],
+            SPItem::Include(item) => vec![&mut item.range, &mut item.v_range],
+        };
+        for range in ranges {
+            shift_range_lines(range, delta);
+        }
+    }
+
+    /// Remove the children of the item and return them.
+    fn take_children(&mut self) -> Vec<Arc<RwLock<SPItem>>> {
+        match self {
+            SPItem::Function(item) => std::mem::take(&mut item.children),
+            SPItem::Enum(item) => std::mem::take(&mut item.children),
+            SPItem::EnumStruct(item) => std::mem::take(&mut item.children),
+            SPItem::Methodmap(item) => std::mem::take(&mut item.children),
+            SPItem::Typeset(item) => std::mem::take(&mut item.children),
+            _ => vec![],
+        }
+    }
+
+    /// Point a child item to the item it was declared in.
+    ///
+    /// # Arguments
+    ///
+    /// * `parent` - Item which contains this item.
+    fn set_parent_item(&mut self, parent: &Arc<RwLock<SPItem>>) {
+        match self {
+            SPItem::Variable(item) => item.parent = Some(Arc::downgrade(parent)),
+            SPItem::Function(item) => item.parent = Some(Arc::downgrade(parent)),
+            SPItem::EnumMember(item) => item.parent = Arc::downgrade(parent),
+            SPItem::Property(item) => item.parent = Arc::downgrade(parent),
+            _ => (),
+        }
+    }
+
+    /// Return a copy of an item and of its children, moved by a number of lines. The item
+    /// itself is left untouched, as it can still be shared with a previous version of its
+    /// document.
+    ///
+    /// # Arguments
+    ///
+    /// * `item` - Item to copy.
+    /// * `delta` - Number of lines to move the copy by, negative to move it up.
+    /// * `parent` - Copy of the parent of the item, if the item is a child.
+    pub(crate) fn shifted_copy(
+        item: &Arc<RwLock<SPItem>>,
+        delta: i32,
+        parent: Option<&Arc<RwLock<SPItem>>>,
+    ) -> Arc<RwLock<SPItem>> {
+        let mut copy = item.read().unwrap().clone();
+        copy.shift_lines(delta);
+        if let Some(parent) = parent {
+            copy.set_parent_item(parent);
+        }
+        let children = copy.take_children();
+        let copy = Arc::new(RwLock::new(copy));
+        for child in children.iter() {
+            let child = SPItem::shifted_copy(child, delta, Some(&copy));
+            copy.write().unwrap().push_child(child);
+        }
+
+        copy
+    }
+
     pub fn push_child(&mut self, child: Arc<RwLock<SPItem>>) {
         match self {
             SPItem::Function(item) => item.children.push(child),
@@ -442,10 +612,7 @@ impl SPItem {
 
     pub fn set_parent(&mut self, parent: Arc<RwLock<SPItem>>) {
         match self {
-            SPItem::Methodmap(item) => {
-                item.parent = Some(parent);
-                item.tmp_parent = None
-            }
+            SPItem::Methodmap(item) => item.parent = Some(parent),
             _ => {
                 log::warn!("Cannot set the methodmap inherits of an item that is not a methodmap.")
             }
@@ -1,15 +1,20 @@
 use crate::providers::hover::description::Description;
+use serde::{Deserialize, Serialize};
 
-#[derive(Debug, Clone)]
+#[derive(Debug, Clone, Serialize, Deserialize)]
 pub struct Parameter {
     pub type_: Option<Type>,
     pub name: String,
     pub is_const: bool,
     pub description: Description,
     pub dimensions: Vec<String>,
+
+    /// Whether the parameter has a default value, e.g `bool replace = false`.
+    #[serde(default)]
+    pub has_default: bool,
 }
 
-#[derive(Debug, Clone)]
+#[derive(Debug, Clone, Serialize, Deserialize)]
 pub struct Type {
     pub is_pointer: bool,
     pub name: String,
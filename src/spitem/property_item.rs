@@ -6,12 +6,13 @@ use lsp_types::{
     CompletionParams, DocumentSymbol, GotoDefinitionParams, Hover, HoverContents, HoverParams,
     LanguageString, LocationLink, MarkedString, Range, SymbolKind, SymbolTag, Url,
 };
+use serde::{Deserialize, Serialize};
 
 use crate::providers::hover::description::Description;
 
 use super::SPItem;
 
-#[derive(Debug, Clone)]
+#[derive(Debug, Clone, Serialize, Deserialize)]
 /// SPItem representation of a SourcePawn property, which can be converted to a
 /// [CompletionItem](lsp_types::CompletionItem), [Location](lsp_types::Location), etc.
 pub struct PropertyItem {
@@ -19,6 +20,7 @@ pub struct PropertyItem {
     pub name: String,
 
     /// Parent of the property.
+    #[serde(skip)]
     pub parent: Weak<RwLock<SPItem>>,
 
     /// Type of the property.
@@ -43,6 +45,7 @@ pub struct PropertyItem {
     pub uri: Arc<Url>,
 
     /// References to this property.
+    #[serde(skip)]
     pub references: Vec<Location>,
 }
 
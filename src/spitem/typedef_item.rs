@@ -1,16 +1,17 @@
 use std::sync::{Arc, RwLock};
 
-use super::{parameter::Parameter, Location};
+use super::{function_item::FunctionItem, parameter::Parameter, Location};
 use lsp_types::{
     CompletionItem, CompletionItemKind, CompletionItemLabelDetails, CompletionItemTag,
     CompletionParams, CompletionTextEdit, DocumentSymbol, GotoDefinitionParams, Hover,
     HoverContents, HoverParams, InsertTextFormat, LanguageString, LocationLink, MarkedString,
     Range, SymbolKind, SymbolTag, TextEdit, Url,
 };
+use serde::{Deserialize, Serialize};
 
 use crate::{providers::hover::description::Description, utils::uri_to_file_name};
 
-#[derive(Debug, Clone)]
+#[derive(Debug, Clone, Serialize, Deserialize)]
 /// SPItem representation of a SourcePawn typedef/functag, which can be converted to a
 /// [CompletionItem](lsp_types::CompletionItem), [Location](lsp_types::Location), etc.
 pub struct TypedefItem {
@@ -42,6 +43,7 @@ pub struct TypedefItem {
     pub detail: String,
 
     /// References to this typedef.
+    #[serde(skip)]
     pub references: Vec<Location>,
 
     /// Parameters of the typedef.
@@ -53,6 +55,30 @@ impl TypedefItem {
         self.description.deprecated.is_some()
     }
 
+    /// Return whether a function has the same return type and parameter types as the typedef,
+    /// and can therefore be used as a callback of this type.
+    ///
+    /// # Arguments
+    ///
+    /// * `function_item` - [FunctionItem] to check.
+    pub(crate) fn is_implemented_by(&self, function_item: &FunctionItem) -> bool {
+        let param_type = |param: &Arc<RwLock<Parameter>>| {
+            param
+                .read()
+                .unwrap()
+                .type_
+                .as_ref()
+                .map(|type_| type_.name.clone())
+        };
+        self.type_ == function_item.type_
+            && self.params.len() == function_item.params.len()
+            && self
+                .params
+                .iter()
+                .zip(function_item.params.iter())
+                .all(|(a, b)| param_type(a) == param_type(b))
+    }
+
     /// Return a [CompletionItem](lsp_types::CompletionItem) from a [TypedefItem].
     ///
     /// # Arguments
@@ -6,10 +6,11 @@ use lsp_types::{
     CompletionParams, DocumentSymbol, GotoDefinitionParams, Hover, HoverContents, HoverParams,
     LanguageString, LocationLink, MarkedString, Range, SymbolKind, SymbolTag, Url,
 };
+use serde::{Deserialize, Serialize};
 
 use crate::{providers::hover::description::Description, utils::uri_to_file_name};
 
-#[derive(Debug, Clone)]
+#[derive(Debug, Clone, Serialize, Deserialize)]
 /// SPItem representation of a SourcePawn typeset/funcenum, which can be converted to a
 /// [CompletionItem](lsp_types::CompletionItem), [Location](lsp_types::Location), etc.
 pub struct TypesetItem {
@@ -35,6 +36,7 @@ pub struct TypesetItem {
     pub uri: Arc<Url>,
 
     /// References to this typeset.
+    #[serde(skip)]
     pub references: Vec<Location>,
 
     /// Parameters of the typeset.
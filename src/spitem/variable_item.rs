@@ -6,12 +6,13 @@ use lsp_types::{
     CompletionParams, DocumentSymbol, GotoDefinitionParams, Hover, HoverContents, HoverParams,
     LanguageString, LocationLink, MarkedString, Range, SymbolKind, SymbolTag, Url,
 };
+use serde::{Deserialize, Serialize};
 
 use crate::{providers::hover::description::Description, utils::range_contains_pos};
 
 use super::SPItem;
 
-#[derive(Debug, Clone)]
+#[derive(Debug, Clone, Serialize, Deserialize)]
 /// SPItem representation of a SourcePawn variable.
 pub struct VariableItem {
     /// Name of the variable.
@@ -42,9 +43,11 @@ pub struct VariableItem {
     pub storage_class: Vec<VariableStorageClass>,
 
     /// References to this variable.
+    #[serde(skip)]
     pub references: Vec<Location>,
 
     /// Parent of this variable, if it is not global.
+    #[serde(skip)]
     pub parent: Option<Weak<RwLock<SPItem>>>,
 }
 
@@ -222,7 +225,7 @@ impl VariableItem {
 }
 
 /// Visibility of a SourcePawn variable.
-#[derive(Debug, PartialEq, Eq, Clone)]
+#[derive(Debug, PartialEq, Eq, Clone, Serialize, Deserialize)]
 pub enum VariableVisibility {
     Public,
     Stock,
@@ -238,7 +241,7 @@ impl VariableVisibility {
 }
 
 /// Storage class of a SourcePawn variable.
-#[derive(Debug, PartialEq, Eq, Clone)]
+#[derive(Debug, PartialEq, Eq, Clone, Serialize, Deserialize)]
 pub enum VariableStorageClass {
     Const,
     Static,
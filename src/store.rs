@@ -8,16 +8,21 @@ use std::{
     sync::{Arc, Mutex, RwLock},
     time::Duration,
 };
-use tree_sitter::Parser;
+use threadpool::ThreadPool;
+use tree_sitter::{Parser, Tree};
 use walkdir::WalkDir;
 
 use crate::{
     document::{Document, Token, Walker},
     environment::Environment,
     parser::include_parser::add_include,
+    providers::semantic_tokens::SemanticTokensCache,
     semantic_analyzer::purge_references,
     spitem::SPItem,
-    utils::{normalize_uri, read_to_string_lossy},
+    utils::{
+        diff_input_edit, normalize_uri, point_to_lsp_position, read_to_string_lossy,
+        shift_range_lines, ts_range_to_lsp_range,
+    },
 };
 
 #[derive(Clone, Default)]
@@ -35,6 +40,23 @@ pub struct Store {
     pub get_all_items_time: Vec<Duration>,
 
     pub get_includes_time: Vec<Duration>,
+
+    /// Incremented each time a document is opened, changed or removed, or its references are
+    /// resolved, so that the results cached for a previous revision can be discarded.
+    pub(crate) revision: u64,
+
+    /// Last semantic tokens sent for each document.
+    pub semantic_tokens_cache: Arc<Mutex<SemanticTokensCache>>,
+
+    /// Id of the last spcomp run requested for each document, used to cancel outdated runs.
+    pub(crate) spcomp_runs: Arc<Mutex<FxHashMap<Url, u64>>>,
+
+    /// Main paths of the workspace, either set in the options or infered when scanning.
+    pub(crate) main_uris: Vec<Url>,
+
+    /// Main path whose includes are used to get the items of the [Store]. Falls back to the
+    /// first main path when [None].
+    pub(crate) scoped_main_uri: Option<Url>,
 }
 
 impl Store {
@@ -66,6 +88,7 @@ impl Store {
         // Open the document as empty to delete the references.
         let _ = self.handle_open_document(&Arc::new((*uri).clone()), "".to_string(), parser);
         self.documents.remove(uri);
+        self.semantic_tokens_cache.lock().unwrap().remove(uri);
         let uri_arc = Arc::new(uri.clone());
         for document in self.documents.values_mut() {
             if let Some(include) = document.includes.get(uri) {
@@ -93,6 +116,24 @@ impl Store {
         }
     }
 
+    /// Discard the unsaved changes of a closed document by reading it again from the disk, or
+    /// remove it if it does not exist on the disk.
+    ///
+    /// # Arguments
+    ///
+    /// * `uri` - [Uri](Url) of the closed document.
+    /// * `parser` - [Parser] to use.
+    pub(crate) fn close(&mut self, uri: &Url, parser: &mut Parser) -> anyhow::Result<()> {
+        match uri.to_file_path() {
+            Ok(path) if path.exists() => {
+                self.reload(path, parser)?;
+            }
+            _ => self.remove(uri, parser),
+        }
+
+        Ok(())
+    }
+
     pub fn register_watcher(&mut self, watcher: notify::RecommendedWatcher) {
         self.watcher = Some(Arc::new(Mutex::new(watcher)));
     }
@@ -206,13 +247,27 @@ impl Store {
         parser: &mut Parser,
     ) -> Result<Document, io::Error> {
         log::trace!("Opening file {:?}", uri);
-        let prev_declarations = match self.documents.get(&(*uri).clone()) {
+        self.revision += 1;
+        let prev_document = self.documents.get(&(*uri).clone()).cloned();
+        let prev_declarations = match &prev_document {
             Some(document) => document.declarations.clone(),
             None => FxHashMap::default(),
         };
         let mut document = Document::new(uri.clone(), text);
         self.preprocess_document(&mut document);
         self.add_sourcemod_include(&mut document);
+        if let Some(prev_document) = prev_document {
+            if let Some(range) = self.parse_incremental(&mut document, &prev_document, parser) {
+                if !self.first_parse {
+                    // The declarations of the document did not change, only resolve the tokens of
+                    // the edited function.
+                    self.find_references_in_range(&(*uri).clone(), range);
+                }
+                log::trace!("Done opening file {:?} incrementally", uri);
+                return Ok(document);
+            }
+            document.tree = prev_document.edited_tree(&document.preprocessed_text);
+        }
         self.parse(&mut document, parser)
             .expect("Couldn't parse document");
         if !self.first_parse {
@@ -225,7 +280,7 @@ impl Store {
         Ok(document)
     }
 
-    fn add_sourcemod_include(&mut self, document: &mut Document) {
+    fn add_sourcemod_include(&self, document: &mut Document) {
         let mut sourcemod_path = "sourcemod".to_string();
         if let Some(uri) = self.resolve_import(&mut sourcemod_path, &document.uri, false) {
             add_include(document, uri, sourcemod_path, Range::default());
@@ -247,7 +302,7 @@ impl Store {
             for item in added_declarations.values() {
                 if sub_doc
                     .unresolved_tokens
-                    .contains(&item.read().unwrap().name())
+                    .contains_key(&item.read().unwrap().name())
                 {
                     to_reload.push(sub_doc.uri.clone());
                     break;
@@ -274,7 +329,11 @@ impl Store {
             if let Some(references) = references {
                 for ref_ in references.iter() {
                     if let Some(ref_document) = self.documents.get_mut(&ref_.uri) {
-                        ref_document.unresolved_tokens.insert(item.name());
+                        ref_document
+                            .unresolved_tokens
+                            .entry(item.name())
+                            .or_default()
+                            .push(ref_.v_range);
                     }
                 }
             }
@@ -293,6 +352,7 @@ impl Store {
         }
         document.being_preprocessed = true;
         let mut preprocessor = SourcepawnPreprocessor::new(document.uri.clone(), &document.text);
+        self.define_predefined_macros(&mut preprocessor);
         let preprocessed_text = preprocessor
             .preprocess_input(
                 &mut (|macros: &mut FxHashMap<String, Macro>,
@@ -310,6 +370,8 @@ impl Store {
         document.preprocessed_text = preprocessed_text;
         document.macros = preprocessor.macros.clone();
         document.offsets = preprocessor.offsets.clone();
+        document.conditional_blocks = preprocessor.conditional_blocks.clone();
+        document.inactive_ranges = preprocessor.inactive_ranges();
         preprocessor.add_diagnostics(&mut document.diagnostics.local_diagnostics);
         document
             .macro_symbols
@@ -342,6 +404,7 @@ impl Store {
         }
         if let Some(text) = self.get_text(&uri) {
             let mut preprocessor = SourcepawnPreprocessor::new(uri.clone(), &text);
+            self.define_predefined_macros(&mut preprocessor);
             let preprocessed_text = preprocessor
                 .preprocess_input(
                     &mut (|macros: &mut FxHashMap<String, Macro>,
@@ -359,6 +422,9 @@ impl Store {
             if let Some(document) = self.documents.get_mut(&uri) {
                 document.preprocessed_text = preprocessed_text;
                 document.macros = preprocessor.macros.clone();
+                document.offsets = preprocessor.offsets.clone();
+                document.conditional_blocks = preprocessor.conditional_blocks.clone();
+                document.inactive_ranges = preprocessor.inactive_ranges();
                 preprocessor.add_diagnostics(&mut document.diagnostics.local_diagnostics);
                 document
                     .macro_symbols
@@ -368,6 +434,7 @@ impl Store {
                             range: token.range,
                         })
                     }));
+                document.being_preprocessed = false;
             }
             return Some(preprocessor.macros);
         }
@@ -379,6 +446,23 @@ impl Store {
         None
     }
 
+    /// Define the macros of the `predefinedMacros` setting in a preprocessor.
+    fn define_predefined_macros(&self, preprocessor: &mut SourcepawnPreprocessor) {
+        for (name, value) in self.environment.options.predefined_macros.iter() {
+            preprocessor.define_macro(name, value);
+        }
+    }
+
+    /// Mark all the documents as not preprocessed and not parsed, so that the next scan
+    /// processes them again from their text.
+    pub(crate) fn invalidate_preprocessing(&mut self) {
+        for document in self.documents.values_mut() {
+            document.preprocessed_text.clear();
+            document.parsed = false;
+            document.tree = None;
+        }
+    }
+
     pub(crate) fn extend_macros(
         &mut self,
         macros: &mut FxHashMap<String, Macro>,
@@ -403,12 +487,41 @@ impl Store {
 
     pub fn parse(&mut self, document: &mut Document, parser: &mut Parser) -> anyhow::Result<()> {
         log::trace!("Parsing document {:?}", document.uri);
+        self.parse_items(document, parser)?;
+        self.documents
+            .insert(document.uri.clone(), document.clone());
+        self.read_unscanned_imports(&document.includes, parser);
+        log::trace!("Done parsing document {:?}", document.uri);
+
+        Ok(())
+    }
+
+    /// Parse a preprocessed document and extract its items, tokens and syntax errors, without
+    /// adding it to the [Store].
+    ///
+    /// # Arguments
+    ///
+    /// * `document` - Preprocessed [Document] to parse.
+    /// * `parser` - [Parser] to use.
+    fn parse_items(&self, document: &mut Document, parser: &mut Parser) -> anyhow::Result<()> {
         let tree = parser
-            .parse(&document.preprocessed_text, None)
+            .parse(&document.preprocessed_text, document.tree.as_ref())
             .ok_or(anyhow!("Failed to parse document {:?}", document.uri))?;
+
+        self.extract_items(document, tree)
+    }
+
+    /// Extract the items, tokens and syntax errors of a preprocessed document from its tree.
+    ///
+    /// # Arguments
+    ///
+    /// * `document` - Preprocessed [Document] to extract the items of.
+    /// * `tree` - [Tree] of the preprocessed text of the document.
+    fn extract_items(&self, document: &mut Document, tree: Tree) -> anyhow::Result<()> {
         let root_node = tree.root_node();
         let mut walker = Walker {
             comments: vec![],
+            comment_blocks: vec![],
             deprecated: vec![],
             anon_enum_counter: 0,
         };
@@ -441,6 +554,11 @@ impl Store {
                 _ => continue,
             };
         }
+        document.comment_blocks = walker
+            .comment_blocks
+            .into_iter()
+            .filter(|range| range.end.line > range.start.line)
+            .collect();
         document.parsed = true;
         document.extract_tokens(root_node);
         document.add_macro_symbols();
@@ -448,12 +566,162 @@ impl Store {
             root_node,
             self.environment.options.disable_syntax_linter,
         );
+        document.tree = Some(tree);
+
+        Ok(())
+    }
+
+    /// Reparse a document by only extracting the items of the function which contains the edit,
+    /// when the edit is inside the body of a top level function. The other items of the previous
+    /// version of the document are kept as is, the ones below the function are moved by the
+    /// inserted or deleted lines.
+    ///
+    /// Return the [Range] of the tokens to resolve again, or [None] if the document has to be
+    /// fully parsed.
+    ///
+    /// # Arguments
+    ///
+    /// * `document` - Preprocessed [Document] to parse.
+    /// * `prev_document` - Previous version of the [Document].
+    /// * `parser` - [Parser] to use.
+    fn parse_incremental(
+        &mut self,
+        document: &mut Document,
+        prev_document: &Document,
+        parser: &mut Parser,
+    ) -> Option<Range> {
+        if !prev_document.parsed || prev_document.preprocessed_text.is_empty() {
+            return None;
+        }
+        let edit = diff_input_edit(
+            &prev_document.preprocessed_text,
+            &document.preprocessed_text,
+        );
+        if edit.start_byte == edit.old_end_byte && edit.start_byte == edit.new_end_byte {
+            return None;
+        }
+        let line_delta = edit.new_end_position.row as i32 - edit.old_end_position.row as i32;
+        let mut old_tree = prev_document.tree.clone()?;
+        let old_end = old_tree.root_node().end_position();
+        old_tree.edit(&edit);
+        let tree = parser.parse(&document.preprocessed_text, Some(&old_tree))?;
+        let root_node = tree.root_node();
+
+        let mut cursor = root_node.walk();
+        let node = root_node.children(&mut cursor).find(|node| {
+            node.start_byte() <= edit.start_byte && edit.new_end_byte <= node.end_byte()
+        })?;
+        if node.kind() != "function_definition" || node.has_error() {
+            return None;
+        }
+        let body_node = node.child_by_field_name("body")?;
+        if edit.start_byte <= body_node.start_byte()
+            || edit.new_end_byte >= body_node.end_byte()
+            || edit.start_position.row <= node.start_position().row
+            || edit.new_end_position.row >= node.end_position().row
+        {
+            // Only handle edits on the inner lines of the body, so that the other items keep
+            // their positions or are moved by whole lines.
+            return None;
+        }
+        let range = ts_range_to_lsp_range(&node.range());
+        let item_idx = prev_document.sp_items.iter().position(|item| {
+            let item = item.read().unwrap();
+            matches!(&*item, SPItem::Function(_)) && item.full_range().start == range.start
+        })?;
+
+        let mut new_document = document.clone();
+        new_document.sp_items = prev_document.sp_items.clone();
+        new_document.declarations = prev_document.declarations.clone();
+        new_document.includes.extend(prev_document.includes.clone());
+        new_document.missing_includes = prev_document.missing_includes.clone();
+        new_document.unresolved_tokens = prev_document.unresolved_tokens.clone();
+        new_document.comment_blocks = prev_document.comment_blocks.clone();
+
+        // Gather the comments and deprecation pragmas which can document the function.
+        let mut walker = Walker {
+            comments: vec![],
+            comment_blocks: vec![],
+            deprecated: vec![],
+            anon_enum_counter: 0,
+        };
+        let mut cursor = root_node.walk();
+        for child in root_node.children(&mut cursor) {
+            if child.start_byte() >= node.start_byte() {
+                break;
+            }
+            match child.kind() {
+                "comment" => walker.push_comment(child, &new_document.preprocessed_text),
+                "preproc_pragma" => {
+                    let _ = walker.push_deprecated(child, &new_document.preprocessed_text);
+                }
+                _ => (),
+            }
+        }
+        new_document.parse_function(&node, &mut walker, None).ok()?;
+        let new_item = new_document.sp_items.pop()?;
+        let old_item = std::mem::replace(&mut new_document.sp_items[item_idx], new_item.clone());
+        // The new function has been declared by the parser, drop the declaration of the old one.
+        new_document
+            .declarations
+            .retain(|_, decl| !Arc::ptr_eq(decl, &old_item));
+        if let Some(references) = old_item.read().unwrap().references() {
+            // Keep the references of the function, they are not affected by the edit.
+            new_item
+                .write()
+                .unwrap()
+                .set_new_references(references.clone());
+        }
+        if line_delta != 0 {
+            // Move what follows the edited function by the inserted or deleted lines.
+            // The items are copied, as they are still shared with the previous version of the
+            // document.
+            let is_below = |range: &Range| range.start.line > edit.start_position.row as u32;
+            for item in new_document.sp_items.iter_mut() {
+                if !is_below(&item.read().unwrap().range()) {
+                    continue;
+                }
+                let copy = SPItem::shifted_copy(item, line_delta, None);
+                replace_declarations(&mut new_document.declarations, item, &copy);
+                *item = copy;
+            }
+            for token in new_document.includes.values_mut() {
+                if is_below(&token.range) {
+                    shift_range_lines(&mut token.range, line_delta);
+                }
+            }
+            for range in new_document
+                .missing_includes
+                .values_mut()
+                .chain(new_document.comment_blocks.iter_mut())
+            {
+                if is_below(range) {
+                    shift_range_lines(range, line_delta);
+                }
+            }
+        }
+
+        new_document.parsed = true;
+        new_document.extract_tokens(root_node);
+        new_document.add_macro_symbols();
+        new_document.get_syntax_error_diagnostics(
+            root_node,
+            self.environment.options.disable_syntax_linter,
+        );
+        new_document.tree = Some(tree.clone());
+        *document = new_document;
         self.documents
             .insert(document.uri.clone(), document.clone());
-        self.read_unscanned_imports(&document.includes, parser);
-        log::trace!("Done parsing document {:?}", document.uri);
 
-        Ok(())
+        if line_delta == 0 {
+            return Some(range);
+        }
+        // The tokens below the function moved as well and have to be resolved again, up to the
+        // end of the longest version of the document.
+        Some(Range::new(
+            range.start,
+            point_to_lsp_position(&old_end.max(root_node.end_position())),
+        ))
     }
 
     pub(crate) fn read_unscanned_imports(
@@ -473,23 +741,140 @@ impl Store {
         }
     }
 
-    pub fn find_all_references(&mut self) {
-        let uris: Vec<Url> =
-            if let Ok(Some(main_path_uri)) = self.environment.options.get_main_path_uri() {
-                let mut includes = FxHashSet::default();
-                includes.insert(main_path_uri.clone());
-                if let Some(document) = self.documents.get(&main_path_uri) {
-                    self.get_included_files(document, &mut includes);
-                    includes.iter().map(|uri| (*uri).clone()).collect()
-                } else {
-                    self.documents.values().map(|doc| doc.uri()).collect()
+    /// Parse documents and the documents they include, building their trees on a [ThreadPool].
+    ///
+    /// The documents are handled level by level: each level is preprocessed sequentially, as the
+    /// macros of a document depend on its includes, then its documents are parsed concurrently.
+    /// The includes which were not parsed yet make up the next level. The references are not
+    /// resolved.
+    ///
+    /// # Arguments
+    ///
+    /// * `uris` - [Uris](Url) of the documents to parse, even if they were already parsed.
+    /// * `pool` - [ThreadPool] to build the trees on.
+    /// * `on_parsed` - Callback called with the [uri](Url) of each parsed document, the number of
+    /// documents parsed so far and the number of documents queued for parsing so far.
+    pub(crate) fn parse_concurrently(
+        &mut self,
+        uris: Vec<Arc<Url>>,
+        pool: &ThreadPool,
+        mut on_parsed: impl FnMut(&Url, usize, usize),
+    ) {
+        let mut done = 0;
+        let mut total = 0;
+        let mut seen = FxHashSet::default();
+        let mut level = uris;
+        let mut force = true;
+        while !level.is_empty() {
+            let mut documents = vec![];
+            for uri in level.drain(..) {
+                if !seen.insert(uri.clone()) {
+                    continue;
                 }
-            } else {
-                self.documents.values().map(|doc| doc.uri()).collect()
+                let mut document = match self.documents.get(&uri) {
+                    // Keep the preprocessing done to expand the macros of the documents which
+                    // include this one.
+                    Some(document)
+                        if !document.parsed && !document.preprocessed_text.is_empty() =>
+                    {
+                        document.clone()
+                    }
+                    Some(document) if force || !document.parsed => {
+                        Document::new(uri, document.text.clone())
+                    }
+                    _ => continue,
+                };
+                self.preprocess_document(&mut document);
+                self.add_sourcemod_include(&mut document);
+                documents.push(document);
+            }
+            force = false;
+            total += documents.len();
+            let mut on_document_parsed = |uri: &Url| {
+                done += 1;
+                on_parsed(uri, done, total);
             };
-        uris.iter().for_each(|uri| {
+            for document in self.parse_items_concurrently(documents, pool, &mut on_document_parsed)
+            {
+                level.extend(
+                    document
+                        .includes
+                        .keys()
+                        .map(|include_uri| Arc::new(include_uri.clone())),
+                );
+                self.documents.insert(document.uri.clone(), document);
+            }
+        }
+    }
+
+    /// Build the trees of preprocessed documents on a [ThreadPool], and extract their items as
+    /// the trees are built.
+    ///
+    /// # Arguments
+    ///
+    /// * `documents` - Preprocessed [documents](Document) to parse.
+    /// * `pool` - [ThreadPool] to build the trees on.
+    /// * `on_parsed` - Callback called with the [uri](Url) of each parsed document.
+    fn parse_items_concurrently(
+        &self,
+        documents: Vec<Document>,
+        pool: &ThreadPool,
+        on_parsed: &mut impl FnMut(&Url),
+    ) -> Vec<Document> {
+        let (parsed_tx, parsed_rx) = crossbeam_channel::unbounded();
+        for document in documents {
+            let parsed_tx = parsed_tx.clone();
+            pool.execute(move || {
+                let mut parser = Parser::new();
+                parser
+                    .set_language(tree_sitter_sourcepawn::language())
+                    .expect("Error loading SourcePawn grammar");
+                let tree = parser.parse(&document.preprocessed_text, None);
+                let _ = parsed_tx.send((document, tree));
+            });
+        }
+        drop(parsed_tx);
+
+        parsed_rx
+            .iter()
+            .map(|(mut document, tree)| {
+                let result = match tree {
+                    Some(tree) => self.extract_items(&mut document, tree),
+                    None => Err(anyhow!("Failed to parse document {:?}", document.uri)),
+                };
+                if let Err(err) = result {
+                    log::error!("Error while parsing file: {}", err);
+                }
+                on_parsed(&document.uri);
+                document
+            })
+            .collect()
+    }
+
+    /// Resolve the references of the documents included by the main paths, or of all the
+    /// documents if there is no main path.
+    ///
+    /// # Arguments
+    ///
+    /// * `on_analyzed` - Callback called with the [uri](Url) of each analyzed document, the
+    /// number of analyzed documents and the total number of documents to analyze.
+    pub fn find_all_references(&mut self, mut on_analyzed: impl FnMut(&Url, usize, usize)) {
+        let mut includes = FxHashSet::default();
+        for main_uri in self.main_uris.iter() {
+            if let Some(document) = self.documents.get(main_uri) {
+                includes.insert(main_uri.clone());
+                self.get_included_files(document, &mut includes);
+            }
+        }
+        let uris: Vec<Url> = if includes.is_empty() {
+            self.documents.values().map(|doc| doc.uri()).collect()
+        } else {
+            includes.into_iter().collect()
+        };
+        for (idx, uri) in uris.iter().enumerate() {
             let _ = self.find_references(uri);
-        });
+            on_analyzed(uri, idx + 1, uris.len());
+        }
     }
 
     pub fn get_all_files_in_folder(&self, folder_uri: &Url) -> Vec<Url> {
@@ -514,7 +899,313 @@ impl Store {
     }
 }
 
+/// Point the declarations of an item and of its children to their copies.
+///
+/// # Arguments
+///
+/// * `declarations` - Declarations of the document of the item.
+/// * `item` - Item which has been copied.
+/// * `copy` - Copy of the item, with the same children in the same order.
+fn replace_declarations(
+    declarations: &mut FxHashMap<String, Arc<RwLock<SPItem>>>,
+    item: &Arc<RwLock<SPItem>>,
+    copy: &Arc<RwLock<SPItem>>,
+) {
+    let copy_item = copy.read().unwrap();
+    if matches!(&*copy_item, SPItem::Include(_)) {
+        return;
+    }
+    let key = copy_item.key();
+    let copy_children = copy_item.children().cloned().unwrap_or_default();
+    // Release the lock, the keys of the children are built from their parent.
+    drop(copy_item);
+    if let Some(decl) = declarations.get_mut(&key) {
+        if Arc::ptr_eq(decl, item) {
+            *decl = copy.clone();
+        }
+    }
+    let children = item.read().unwrap().children().cloned().unwrap_or_default();
+    for (child, child_copy) in children.iter().zip(copy_children.iter()) {
+        replace_declarations(declarations, child, child_copy);
+    }
+}
+
 fn is_git_folder(path: &Path) -> bool {
     let file_name = path.file_name().unwrap_or_default();
     file_name == ".git"
 }
+
+#[cfg(test)]
+mod tests {
+    use std::sync::{Arc, RwLock};
+
+    use lsp_types::Url;
+    use tree_sitter::Parser;
+
+    use super::Store;
+    use crate::{document::Document, spitem::SPItem, tests::fixtures::TestStore};
+
+    const TEXT: &str = r#"int foo;
+
+void bar()
+{
+    int a = foo;
+}
+
+void baz()
+{
+    bar();
+    foo = Color_Red;
+}
+
+enum Color
+{
+    Color_Red,
+    Color_Blue
+}
+"#;
+
+    fn parser() -> Parser {
+        let mut parser = Parser::new();
+        parser
+            .set_language(tree_sitter_sourcepawn::language())
+            .unwrap();
+
+        parser
+    }
+
+    /// Open each version of a document one after the other and return the last one.
+    fn open(versions: &[&str]) -> (Store, Document) {
+        let uri = Arc::new(Url::parse("file:///test.sp").unwrap());
+        let mut parser = parser();
+        let mut store = Store::new(false);
+        store.first_parse = false;
+        let mut document = None;
+        for text in versions {
+            document = Some(
+                store
+                    .handle_open_document(&uri, text.to_string(), &mut parser)
+                    .unwrap(),
+            );
+        }
+
+        (store, document.unwrap())
+    }
+
+    fn describe(items: &[Arc<RwLock<SPItem>>], with_references: bool) -> Vec<String> {
+        let mut res = vec![];
+        for item in items {
+            let item = item.read().unwrap();
+            let mut references = vec![];
+            if with_references {
+                references = item
+                    .references()
+                    .into_iter()
+                    .flatten()
+                    .map(|reference| format!("{:?} {:?}", reference.range, reference.v_range))
+                    .collect();
+                references.sort();
+            }
+            res.push(format!(
+                "{} {:?} {:?} {:?} {:?}",
+                item.name(),
+                item.range(),
+                item.v_range(),
+                item.full_range(),
+                references
+            ));
+            res.extend(describe(
+                item.children()
+                    .map(|children| children.as_slice())
+                    .unwrap_or_default(),
+                with_references,
+            ));
+        }
+
+        res
+    }
+
+    fn flatten(items: &[Arc<RwLock<SPItem>>]) -> Vec<Arc<RwLock<SPItem>>> {
+        let mut res = vec![];
+        for item in items {
+            res.push(item.clone());
+            res.extend(flatten(
+                item.read()
+                    .unwrap()
+                    .children()
+                    .map(|children| children.as_slice())
+                    .unwrap_or_default(),
+            ));
+        }
+
+        res
+    }
+
+    /// Check that the incremental parse of `edited` gives the same document as a full parse,
+    /// and that the previous version of the document was left untouched.
+    fn assert_incremental_parse(edited: &str) {
+        let (mut store, prev_document) = open(&[TEXT]);
+        let prev_items = describe(&prev_document.sp_items, false);
+        let document = store
+            .handle_open_document(&prev_document.uri, edited.to_string(), &mut parser())
+            .unwrap();
+        let (_, expected) = open(&[edited]);
+
+        // The items above the edited function are only shared when the document is parsed
+        // incrementally.
+        assert!(Arc::ptr_eq(
+            &document.sp_items[0],
+            &prev_document.sp_items[0]
+        ));
+        assert_eq!(
+            describe(&document.sp_items, true),
+            describe(&expected.sp_items, true)
+        );
+        assert_eq!(describe(&prev_document.sp_items, false), prev_items);
+
+        let items = flatten(&document.sp_items);
+        let mut keys: Vec<_> = document.declarations.keys().cloned().collect();
+        let mut expected_keys: Vec<_> = expected.declarations.keys().cloned().collect();
+        keys.sort();
+        expected_keys.sort();
+        assert_eq!(keys, expected_keys);
+        for decl in document.declarations.values() {
+            assert!(items.iter().any(|item| Arc::ptr_eq(item, decl)));
+        }
+        for item in items.iter() {
+            if let SPItem::EnumMember(member) = &*item.read().unwrap() {
+                let parent = member.parent.upgrade().unwrap();
+                assert!(items.iter().any(|item| Arc::ptr_eq(item, &parent)));
+            }
+        }
+    }
+
+    #[test]
+    fn parse_incremental_same_lines() {
+        assert_incremental_parse(&TEXT.replace("int a = foo;", "int ab = foo + 1;"));
+    }
+
+    #[test]
+    fn parse_incremental_added_lines() {
+        assert_incremental_parse(&TEXT.replace(
+            "    int a = foo;\n",
+            "    int a = foo;\n    int b = a;\n    b = Color_Blue;\n",
+        ));
+    }
+
+    #[test]
+    fn close_discards_the_unsaved_changes() {
+        let mut test_store =
+            TestStore::new("//! main.sp\nint foo;\n//! other.sp\nint other;").unwrap();
+        let uri = test_store.uri("main.sp");
+        let mut parser = TestStore::parser();
+        test_store
+            .store
+            .handle_open_document(&uri, "int bar;\n".to_string(), &mut parser)
+            .unwrap();
+        assert!(test_store.store.documents[&uri]
+            .declarations
+            .keys()
+            .any(|key| key == "bar"));
+
+        test_store.store.close(&uri, &mut parser).unwrap();
+        let document = &test_store.store.documents[&uri];
+        assert_eq!(document.text, "int foo;");
+        assert!(document.declarations.keys().any(|key| key == "foo"));
+        assert!(!document.declarations.keys().any(|key| key == "bar"));
+    }
+
+    #[test]
+    fn close_removes_the_documents_which_are_not_on_disk() {
+        let mut test_store = TestStore::new("//! main.sp\nint foo;").unwrap();
+        let uri = Arc::new(Url::parse("untitled:Untitled-1").unwrap());
+        let mut parser = TestStore::parser();
+        test_store
+            .store
+            .handle_open_document(&uri, "int bar;\n".to_string(), &mut parser)
+            .unwrap();
+
+        test_store.store.close(&uri, &mut parser).unwrap();
+        assert!(!test_store.store.documents.contains_key(&uri));
+    }
+
+    const INCLUDES: &str = r#"
+//! main.sp
+#include "foo.inc"
+int main;
+
+//! foo.inc
+#include "bar.inc"
+int foo;
+
+//! bar.inc
+int bar;
+
+//! other.inc
+int other;
+"#;
+
+    #[test]
+    fn parse_concurrently_follows_the_includes() {
+        let test_store = TestStore::new(INCLUDES).unwrap();
+        let mut store = Store::new(false);
+        store.find_documents(&test_store.directory().to_path_buf());
+        let mut parsed = vec![];
+        store.parse_concurrently(
+            vec![test_store.uri("main.sp")],
+            &threadpool::Builder::new().build(),
+            |uri, done, total| parsed.push((uri.clone(), done, total)),
+        );
+
+        assert_eq!(
+            parsed,
+            vec![
+                ((*test_store.uri("main.sp")).clone(), 1, 1),
+                ((*test_store.uri("foo.inc")).clone(), 2, 2),
+                ((*test_store.uri("bar.inc")).clone(), 3, 3),
+            ]
+        );
+        for path in ["main.sp", "foo.inc", "bar.inc"] {
+            assert!(store.documents[&test_store.uri(path)].parsed);
+        }
+        assert!(!store.documents[&test_store.uri("other.inc")].parsed);
+    }
+
+    #[test]
+    fn parse_concurrently_only_reparses_the_requested_documents() {
+        let mut test_store = TestStore::new(INCLUDES).unwrap();
+        let uri = test_store.uri("main.sp");
+        let foo_document = test_store.store.documents[&test_store.uri("foo.inc")].clone();
+        let mut parsed = vec![];
+        test_store.store.parse_concurrently(
+            vec![uri.clone()],
+            &threadpool::Builder::new().build(),
+            |uri, _, _| parsed.push(uri.clone()),
+        );
+
+        assert_eq!(parsed, vec![(*uri).clone()]);
+        assert!(Arc::ptr_eq(
+            &test_store.store.documents[&test_store.uri("foo.inc")].sp_items[0],
+            &foo_document.sp_items[0]
+        ));
+        assert!(test_store.store.documents[&uri]
+            .declarations
+            .keys()
+            .any(|key| key == "main"));
+    }
+
+    #[test]
+    fn parse_incremental_removed_lines() {
+        let text = TEXT.replace(
+            "    int a = foo;\n",
+            "    int a = foo;\n    int b = a;\n    b = Color_Blue;\n",
+        );
+        let (_, document) = open(&[&text, TEXT]);
+        let (_, expected) = open(&[TEXT]);
+
+        assert_eq!(
+            describe(&document.sp_items, true),
+            describe(&expected.sp_items, true)
+        );
+    }
+}
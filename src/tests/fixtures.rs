@@ -13,8 +13,9 @@ use lsp_types::{
     Position, Range, TextDocumentIdentifier, TextDocumentItem, TextDocumentPositionParams, Url,
 };
 use tempfile::{tempdir, TempDir};
+use tree_sitter::Parser;
 
-use crate::{document::Document, LspClient, Server};
+use crate::{document::Document, store::Store, LspClient, Server};
 
 #[derive(Debug)]
 pub struct Fixture {
@@ -257,3 +258,78 @@ impl TestBed {
         Url::parse(&uri.as_str().replace("file:///C:/", "file:///")).unwrap()
     }
 }
+
+/// [Store] with the documents of a fixture, opened and resolved as the server does at startup,
+/// for the tests which don't need a server.
+pub struct TestStore {
+    fixture: Fixture,
+    directory: TempDir,
+    pub store: Store,
+}
+
+impl TestStore {
+    pub fn new(fixture: &str) -> Result<Self> {
+        let fixture = Fixture::parse(fixture);
+        let directory = tempdir()?;
+        for document in &fixture.documents {
+            let path = directory.path().join(&document.path);
+            std::fs::create_dir_all(path.parent().unwrap())?;
+            std::fs::write(&path, &document.text)?;
+        }
+        let mut store = Store::new(false);
+        store.find_documents(&directory.path().to_path_buf());
+        let uris: Vec<Arc<Url>> = store.documents.keys().cloned().collect();
+        store.parse_concurrently(uris, &threadpool::Builder::new().build(), |_, _, _| ());
+        store.find_all_references(|_, _, _| ());
+        store.first_parse = false;
+
+        Ok(Self {
+            fixture,
+            directory,
+            store,
+        })
+    }
+
+    pub fn parser() -> Parser {
+        let mut parser = Parser::new();
+        parser
+            .set_language(tree_sitter_sourcepawn::language())
+            .unwrap();
+
+        parser
+    }
+
+    pub fn uri(&self, path: &str) -> Arc<Url> {
+        Arc::new(Url::from_file_path(self.directory.path().join(path)).unwrap())
+    }
+
+    pub fn cursor(&self) -> Option<TextDocumentPositionParams> {
+        let (document, cursor) = self
+            .fixture
+            .documents
+            .iter()
+            .find_map(|document| document.cursor.map(|cursor| (document, cursor)))?;
+
+        let uri = Url::from_file_path(self.directory.path().join(&document.path)).unwrap();
+        let id = TextDocumentIdentifier::new(uri);
+        Some(TextDocumentPositionParams::new(id, cursor))
+    }
+
+    pub fn locations(&self) -> Vec<Location> {
+        self.fixture
+            .documents
+            .iter()
+            .flat_map(|document| {
+                let uri = Url::from_file_path(self.directory.path().join(&document.path)).unwrap();
+                document
+                    .ranges
+                    .iter()
+                    .map(move |range| Location::new(uri.clone(), *range))
+            })
+            .collect()
+    }
+
+    pub fn directory(&self) -> &Path {
+        self.directory.path()
+    }
+}
@@ -83,6 +83,60 @@ pub fn apply_document_edit(old_text: &mut String, changes: Vec<TextDocumentConte
     }
 }
 
+/// Build the [InputEdit](tree_sitter::InputEdit) which turns a text into another one, by
+/// replacing everything between their common prefix and their common suffix.
+///
+/// # Arguments
+///
+/// * `old_text` - Text before the edit.
+/// * `new_text` - Text after the edit.
+pub fn diff_input_edit(old_text: &str, new_text: &str) -> tree_sitter::InputEdit {
+    let old_bytes = old_text.as_bytes();
+    let new_bytes = new_text.as_bytes();
+    let prefix = old_bytes
+        .iter()
+        .zip(new_bytes.iter())
+        .take_while(|(a, b)| a == b)
+        .count();
+    let suffix = old_bytes[prefix..]
+        .iter()
+        .rev()
+        .zip(new_bytes[prefix..].iter().rev())
+        .take_while(|(a, b)| a == b)
+        .count();
+    let old_end_byte = old_bytes.len() - suffix;
+    let new_end_byte = new_bytes.len() - suffix;
+
+    tree_sitter::InputEdit {
+        start_byte: prefix,
+        old_end_byte,
+        new_end_byte,
+        start_position: byte_to_point(old_bytes, prefix),
+        old_end_position: byte_to_point(old_bytes, old_end_byte),
+        new_end_position: byte_to_point(new_bytes, new_end_byte),
+    }
+}
+
+/// Convert a byte offset in a text to a Tree-sitter [Point](tree_sitter::Point).
+///
+/// # Arguments
+///
+/// * `bytes` - Bytes of the text.
+/// * `offset` - Offset to convert.
+fn byte_to_point(bytes: &[u8], offset: usize) -> tree_sitter::Point {
+    let before = &bytes[..offset];
+    match before.iter().rposition(|byte| *byte == b'\n') {
+        Some(line_start) => tree_sitter::Point {
+            row: before.iter().filter(|byte| **byte == b'\n').count(),
+            column: offset - line_start - 1,
+        },
+        None => tree_sitter::Point {
+            row: 0,
+            column: offset,
+        },
+    }
+}
+
 /// Returns true if a [Position] is contained in a [Range].
 ///
 /// # Arguments
@@ -140,6 +194,17 @@ pub fn range_equals_range(a: &Range, b: &Range) -> bool {
     true
 }
 
+/// Moves a [range](lsp_types::Range) by a number of lines.
+///
+/// # Arguments
+///
+/// * `range` - [Range](lsp_types::Range) to move.
+/// * `delta` - Number of lines to move the range by, negative to move it up.
+pub fn shift_range_lines(range: &mut Range, delta: i32) {
+    range.start.line = range.start.line.saturating_add_signed(delta);
+    range.end.line = range.end.line.saturating_add_signed(delta);
+}
+
 /// Returns the arithmetic average of a [range](lsp_types::Range) as a [position](lsp_types::Position).
 ///
 /// # Arguments
@@ -180,3 +245,58 @@ pub fn read_to_string_lossy(path: PathBuf) -> anyhow::Result<String> {
 
     Ok(String::from_utf8_lossy(&buf).to_string())
 }
+
+/// Return the Levenshtein distance between two strings, i.e the number of characters to insert,
+/// delete or substitute to go from one to the other.
+///
+/// # Arguments
+///
+/// * `a` - First string.
+/// * `b` - Second string.
+pub fn edit_distance(a: &str, b: &str) -> usize {
+    let b: Vec<char> = b.chars().collect();
+    let mut row: Vec<usize> = (0..=b.len()).collect();
+    for (i, a_char) in a.chars().enumerate() {
+        let mut diagonal = row[0];
+        row[0] = i + 1;
+        for (j, b_char) in b.iter().enumerate() {
+            let substitution = diagonal + usize::from(a_char != *b_char);
+            diagonal = row[j + 1];
+            row[j + 1] = substitution.min(row[j] + 1).min(diagonal + 1);
+        }
+    }
+
+    row[b.len()]
+}
+
+#[cfg(test)]
+mod tests {
+    use super::{diff_input_edit, edit_distance};
+
+    #[test]
+    fn diff_input_edit_insertion() {
+        let edit = diff_input_edit("int foo;\nint bar;\n", "int foo;\nint barbaz;\n");
+        assert_eq!(edit.start_byte, 16);
+        assert_eq!(edit.old_end_byte, 16);
+        assert_eq!(edit.new_end_byte, 19);
+        assert_eq!(edit.start_position.row, 1);
+        assert_eq!(edit.start_position.column, 7);
+        assert_eq!(edit.new_end_position.column, 10);
+    }
+
+    #[test]
+    fn diff_input_edit_new_line() {
+        let edit = diff_input_edit("a\nb", "a\n\nb");
+        assert_eq!(edit.start_position.row, 1);
+        assert_eq!(edit.old_end_position.row, 1);
+        assert_eq!(edit.new_end_position.row, 2);
+    }
+
+    #[test]
+    fn edit_distance_counts_edits() {
+        assert_eq!(edit_distance("PrintToChat", "PrintToChat"), 0);
+        assert_eq!(edit_distance("PrintToChta", "PrintToChat"), 2);
+        assert_eq!(edit_distance("GetClientNam", "GetClientName"), 1);
+        assert_eq!(edit_distance("", "abc"), 3);
+    }
+}